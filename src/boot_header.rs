@@ -0,0 +1,321 @@
+//! Boot image header of the LPC1800 and LPC4300 famillies.
+//!
+//! When booting from SPIFI, EMC or USB, the BootROM expects a 16 bytes header in front of the
+//! image, giving the size of the image to load and whether it's AES encrypted or hashed.
+//!
+//! The LPC18Sxx and LPC43Sxx parts boot images encrypted by AES-128 in CBC mode with the user
+//! key programmed in OTP, the hash value holding the first 8 bytes of the AES-CMAC of the
+//! encrypted image.
+
+use crate::{Error, Result};
+#[cfg(feature = "secure")]
+use aes::Aes128;
+#[cfg(feature = "secure")]
+use cbc::cipher::{BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+#[cfg(feature = "secure")]
+use cmac::{Cmac, Mac};
+use core::convert::TryInto;
+
+/// Size in bytes of the boot image header.
+pub const BOOT_HEADER_SIZE: usize = 16;
+
+/// Size in bytes of the blocks used for the image size.
+pub const BLOCK_SIZE: usize = 512;
+
+/// AES_ACTIVE value of an encrypted image.
+const AES_ACTIVE: u8 = 0x25;
+
+/// AES_ACTIVE value of a plain image.
+const AES_NOT_ACTIVE: u8 = 0xDA;
+
+/// HASH_ACTIVE value of an image with a hash value.
+const HASH_ACTIVE: u32 = 0b00;
+
+/// HASH_ACTIVE value of an image without hash value.
+const HASH_NOT_ACTIVE: u32 = 0b11;
+
+/// Size in bytes of the AES-128 user key.
+#[cfg(feature = "secure")]
+pub const KEY_SIZE: usize = 16;
+
+/// Size in bytes of the AES blocks.
+#[cfg(feature = "secure")]
+const AES_BLOCK_SIZE: usize = 16;
+
+/// Boot image header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(serde::Serialize))]
+pub struct BootHeader {
+    /// Whether the image is AES encrypted.
+    pub aes_active: bool,
+    /// Whether the hash value is checked.
+    pub hash_active: bool,
+    /// The AES_CONTROL bits, keeping the header distinguishable from encrypted data.
+    pub aes_control: u8,
+    /// The size of the image, and of the hashed area, in blocks of 512 bytes.
+    pub hash_size: u16,
+    /// The AES-CMAC hash value of the image.
+    pub hash_value: u64,
+}
+
+impl BootHeader {
+    /// Create the header of a plain image of the given size in bytes.
+    pub fn new(image_size: usize) -> Result<Self> {
+        let hash_size = image_size.div_ceil(BLOCK_SIZE);
+
+        Ok(BootHeader {
+            aes_active: false,
+            hash_active: false,
+            aes_control: 0b11,
+            hash_size: hash_size
+                .try_into()
+                .map_err(|_| Error::InvalidBootHeader("image too large"))?,
+            hash_value: u64::MAX,
+        })
+    }
+
+    /// Parse the header at the start of the given image.
+    pub fn parse(image: &[u8]) -> Result<Self> {
+        let header = image.get(..BOOT_HEADER_SIZE).ok_or(Error::ImageTooShort {
+            required: BOOT_HEADER_SIZE,
+            actual: image.len(),
+        })?;
+        let word = u32::from_le_bytes(header[..4].try_into().unwrap());
+
+        let aes_active = match header[0] {
+            AES_ACTIVE => true,
+            AES_NOT_ACTIVE => false,
+            _ => return Err(Error::InvalidBootHeader("invalid AES_ACTIVE field")),
+        };
+
+        let hash_active = match (word >> 8) & 0b11 {
+            HASH_ACTIVE => true,
+            HASH_NOT_ACTIVE => false,
+            _ => return Err(Error::InvalidBootHeader("invalid HASH_ACTIVE field")),
+        };
+
+        let hash_size = (word >> 16) as u16;
+
+        if hash_size == 0 {
+            return Err(Error::InvalidBootHeader("empty image"));
+        }
+
+        Ok(BootHeader {
+            aes_active,
+            hash_active,
+            aes_control: ((word >> 14) & 0b11) as u8,
+            hash_size,
+            hash_value: u64::from_le_bytes(header[4..12].try_into().unwrap()),
+        })
+    }
+
+    /// The size in bytes of the image loaded by the BootROM.
+    pub fn image_size(&self) -> usize {
+        usize::from(self.hash_size) * BLOCK_SIZE
+    }
+
+    /// Encode the header.
+    pub fn to_bytes(&self) -> [u8; BOOT_HEADER_SIZE] {
+        let aes_active = if self.aes_active {
+            AES_ACTIVE
+        } else {
+            AES_NOT_ACTIVE
+        };
+        let hash_active = if self.hash_active {
+            HASH_ACTIVE
+        } else {
+            HASH_NOT_ACTIVE
+        };
+        // The reserved bits are programmed as ones.
+        let word = u32::from(aes_active)
+            | hash_active << 8
+            | 0b1111 << 10
+            | u32::from(self.aes_control & 0b11) << 14
+            | u32::from(self.hash_size) << 16;
+
+        let mut header = [0xFF; BOOT_HEADER_SIZE];
+        header[..4].copy_from_slice(&word.to_le_bytes());
+        header[4..12].copy_from_slice(&self.hash_value.to_le_bytes());
+
+        header
+    }
+
+    /// Compute the hash value of the image loaded by the BootROM.
+    #[cfg(feature = "secure")]
+    pub fn compute_hash(&self, image: &[u8], key: &[u8; KEY_SIZE]) -> Result<u64> {
+        let image = image.get(..self.image_size()).ok_or(Error::ImageTooShort {
+            required: self.image_size(),
+            actual: image.len(),
+        })?;
+        let mut mac = Cmac::<Aes128>::new(key.into());
+        mac.update(image);
+
+        Ok(u64::from_le_bytes(
+            mac.finalize().into_bytes()[..8].try_into().unwrap(),
+        ))
+    }
+
+    /// Check the hash value of the image if active, then decrypt the image if encrypted.
+    #[cfg(feature = "secure")]
+    pub fn decrypt(&self, image: &mut [u8], key: &[u8; KEY_SIZE]) -> Result<()> {
+        if image.len() < self.image_size() {
+            return Err(Error::ImageTooShort {
+                required: self.image_size(),
+                actual: image.len(),
+            });
+        }
+
+        if self.hash_active && self.compute_hash(image, key)? != self.hash_value {
+            return Err(Error::InvalidBootHeader(
+                "hash value mismatch, check the AES key",
+            ));
+        }
+
+        if self.aes_active {
+            let mut cipher = cbc::Decryptor::<Aes128>::new(key.into(), &Default::default());
+
+            for block in image[..self.image_size()].chunks_exact_mut(AES_BLOCK_SIZE) {
+                cipher.decrypt_block_mut(block.into());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Encrypt the image with the AES key, after padding it to the blocks loaded by the BootROM as
+/// erased flash, and create its header.
+#[cfg(feature = "secure")]
+pub fn encrypt(image: &mut Vec<u8>, key: &[u8; KEY_SIZE]) -> Result<BootHeader> {
+    let mut header = BootHeader::new(image.len())?;
+    header.aes_active = true;
+    header.hash_active = true;
+
+    image.resize(header.image_size(), 0xFF);
+
+    let mut cipher = cbc::Encryptor::<Aes128>::new(key.into(), &Default::default());
+
+    for block in image.chunks_exact_mut(AES_BLOCK_SIZE) {
+        cipher.encrypt_block_mut(block.into());
+    }
+
+    header.hash_value = header.compute_hash(image, key)?;
+
+    Ok(header)
+}
+
+/// Parse and remove the boot image header at the start of the image, decrypting secure images
+/// with the AES key if given.
+#[cfg(feature = "std")]
+pub fn strip(
+    image: &mut Vec<u8>,
+    #[cfg(feature = "secure")] key: Option<&[u8; KEY_SIZE]>,
+) -> Result<BootHeader> {
+    let boot_header = BootHeader::parse(image)?;
+    image.drain(..BOOT_HEADER_SIZE);
+
+    if image.len() > boot_header.image_size() {
+        return Err(Error::InvalidBootHeader(
+            "image larger than the size in the header",
+        ));
+    }
+
+    #[cfg(feature = "secure")]
+    let decrypted = match key {
+        Some(key) => boot_header.decrypt(image, key).map(|_| true)?,
+        None => false,
+    };
+    #[cfg(not(feature = "secure"))]
+    let decrypted = false;
+
+    if boot_header.aes_active && !decrypted {
+        return Err(Error::InvalidBootHeader(
+            "encrypted image, checking it needs the AES key",
+        ));
+    }
+
+    Ok(boot_header)
+}
+
+/// Create the boot image header to prepend to the image, refusing images already starting with
+/// a header unless forced.
+///
+/// `handler` is called with a warning if a forced image already starts with a header.
+#[cfg(feature = "std")]
+pub fn create<F: FnMut(String)>(image: &[u8], force: bool, mut handler: F) -> Result<BootHeader> {
+    if BootHeader::parse(image).is_ok() {
+        if !force {
+            return Err(Error::InvalidBootHeader("image already has a boot header"));
+        }
+
+        handler("Image already starts with a boot image header".to_string());
+    }
+
+    BootHeader::new(image.len())
+}
+
+#[cfg(all(test, feature = "secure"))]
+mod tests {
+    use super::*;
+
+    const KEY: [u8; KEY_SIZE] = [
+        0x2B, 0x7E, 0x15, 0x16, 0x28, 0xAE, 0xD2, 0xA6, 0xAB, 0xF7, 0x15, 0x88, 0x09, 0xCF, 0x4F,
+        0x3C,
+    ];
+
+    #[test]
+    fn header_round_trip() {
+        let header = BootHeader::new(1000).unwrap();
+
+        assert_eq!(header.image_size(), 2 * BLOCK_SIZE);
+        assert_eq!(BootHeader::parse(&header.to_bytes()).unwrap(), header);
+    }
+
+    #[test]
+    fn encrypt_decrypt() {
+        let plain = (0..1000).map(|i| i as u8).collect::<Vec<_>>();
+        let mut image = plain.clone();
+        let header = encrypt(&mut image, &KEY).unwrap();
+
+        assert!(header.aes_active && header.hash_active);
+        assert_eq!(image.len(), header.image_size());
+        assert_ne!(image[..plain.len()], plain[..]);
+
+        let parsed = BootHeader::parse(&header.to_bytes()).unwrap();
+        assert_eq!(parsed, header);
+
+        let mut decrypted = image.clone();
+        parsed.decrypt(&mut decrypted, &KEY).unwrap();
+        assert_eq!(decrypted[..plain.len()], plain[..]);
+        assert!(decrypted[plain.len()..].iter().all(|byte| *byte == 0xFF));
+
+        // A wrong key or a modified image doesn't match the hash value.
+        let mut wrong_key = KEY;
+        wrong_key[0] ^= 1;
+        assert!(matches!(
+            parsed.decrypt(&mut image.clone(), &wrong_key),
+            Err(Error::InvalidBootHeader(_))
+        ));
+
+        let mut tampered = image.clone();
+        tampered[100] ^= 1;
+        assert!(matches!(
+            parsed.decrypt(&mut tampered, &KEY),
+            Err(Error::InvalidBootHeader(_))
+        ));
+
+        // The image must hold all the blocks loaded by the BootROM.
+        let mut short = image[..image.len() - 16].to_vec();
+        let plain_header = BootHeader {
+            hash_active: false,
+            ..parsed
+        };
+        assert!(matches!(
+            plain_header.decrypt(&mut short, &KEY),
+            Err(Error::ImageTooShort {
+                required: 1024,
+                actual: 1008
+            })
+        ));
+    }
+}
@@ -0,0 +1,82 @@
+//! Allocation free checksum arithmetic, usable from bootloaders and firmware.
+
+/// Compute the checksum of the given vector table words.
+///
+/// The word at `resulting_word_position` is ignored, the result being the value to store there.
+pub fn compute_checksum<I: IntoIterator<Item = u32>>(
+    words: I,
+    resulting_word_position: usize,
+) -> u32 {
+    let checksum = words
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| *i != resulting_word_position)
+        .fold(0u32, |checksum, (_, word)| checksum.wrapping_add(word));
+
+    0u32.wrapping_sub(checksum)
+}
+
+/// Compute the checksum of the vector table located at the given address.
+///
+/// # Safety
+///
+/// `address` must be aligned and valid for reads of `words_count` words.
+pub unsafe fn compute_checksum_raw(
+    address: *const u32,
+    words_count: usize,
+    resulting_word_position: usize,
+) -> u32 {
+    compute_checksum(
+        (0..words_count).map(|i| core::ptr::read_volatile(address.add(i))),
+        resulting_word_position,
+    )
+}
+
+/// Compute the CRC32 of the given bytes, as checked by the LPC5400 and LPC5500 BootROM.
+///
+/// The CRC uses the 0x04C11DB7 polynomial without reflection, starting from 0xFFFFFFFF.
+pub fn crc32<'a, I: IntoIterator<Item = &'a u8>>(data: I) -> u32 {
+    data.into_iter().fold(0xFFFF_FFFF, |crc, byte| {
+        (0..8).fold(crc ^ (u32::from(*byte) << 24), |crc, _| {
+            if crc & 0x8000_0000 != 0 {
+                crc << 1 ^ 0x04C1_1DB7
+            } else {
+                crc << 1
+            }
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checksum_skips_its_position() {
+        assert_eq!(
+            compute_checksum([1, 2, 3, 4, 5, 6, 7], 7),
+            0u32.wrapping_sub(28)
+        );
+        assert_eq!(
+            compute_checksum([1, 2, 3, 4, 5, 0xDEAD_BEEF, 7, 8], 5),
+            0u32.wrapping_sub(30)
+        );
+        assert_eq!(compute_checksum([u32::MAX, 2], 7), u32::MAX);
+    }
+
+    #[test]
+    fn raw_vector_table() {
+        let vector_table = [0x1000_2000, 0x101, 0x103, 0x105, 0x107, 0x109, 0x10B, 0];
+
+        assert_eq!(
+            unsafe { compute_checksum_raw(vector_table.as_ptr(), 7, 7) },
+            compute_checksum(vector_table, 7)
+        );
+    }
+
+    #[test]
+    fn crc32_check_value() {
+        assert_eq!(crc32(b"123456789"), 0x0376_E6E7);
+        assert_eq!(crc32(&[]), 0xFFFF_FFFF);
+    }
+}
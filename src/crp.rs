@@ -0,0 +1,218 @@
+//! Code Read Protection (CRP) word handling.
+//!
+//! The BootROM reads the CRP word from a fixed location of the flash to restrict the debug and
+//! ISP access. CRP3 permanently disables ISP, making it impossible to reflash the part without
+//! a firmware update mechanism.
+
+use crate::part::Family;
+use crate::{Error, Result};
+
+/// Offset of the CRP word on ARM7 parts.
+pub const ARM7_CRP_OFFSET: usize = 0x1FC;
+
+/// Offset of the CRP word on Cortex-M parts.
+pub const CORTEX_M_CRP_OFFSET: usize = 0x2FC;
+
+/// Level of Code Read Protection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrpLevel {
+    /// No protection.
+    None,
+    /// ISP entry pin disabled.
+    NoIsp,
+    /// Debug disabled, partial flash update through ISP.
+    Crp1,
+    /// Debug disabled, only full chip erase through ISP.
+    Crp2,
+    /// Debug and ISP permanently disabled.
+    Crp3,
+}
+
+impl CrpLevel {
+    /// All the CRP levels.
+    pub const ALL: &'static [CrpLevel] = &[
+        CrpLevel::None,
+        CrpLevel::NoIsp,
+        CrpLevel::Crp1,
+        CrpLevel::Crp2,
+        CrpLevel::Crp3,
+    ];
+
+    /// Decode a CRP word, any unknown value meaning no protection.
+    pub fn from_word(word: u32) -> Self {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|level| level.is_protected() && level.word() == word)
+            .unwrap_or(CrpLevel::None)
+    }
+
+    /// Get a CRP level by its name, ignoring the case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|level| level.name().eq_ignore_ascii_case(name))
+    }
+
+    /// The CRP word enabling this level, the erased flash value for no protection.
+    pub fn word(self) -> u32 {
+        match self {
+            CrpLevel::None => 0xFFFF_FFFF,
+            CrpLevel::NoIsp => 0x4E69_7370,
+            CrpLevel::Crp1 => 0x1234_5678,
+            CrpLevel::Crp2 => 0x8765_4321,
+            CrpLevel::Crp3 => 0x4321_8765,
+        }
+    }
+
+    /// The name of the CRP level.
+    pub fn name(self) -> &'static str {
+        match self {
+            CrpLevel::None => "NONE",
+            CrpLevel::NoIsp => "NO_ISP",
+            CrpLevel::Crp1 => "CRP1",
+            CrpLevel::Crp2 => "CRP2",
+            CrpLevel::Crp3 => "CRP3",
+        }
+    }
+
+    /// Returns true if the level restricts the access to the part.
+    pub fn is_protected(self) -> bool {
+        self != CrpLevel::None
+    }
+}
+
+/// Get the offset of the CRP word in the image for the given familly.
+///
+/// Returns `None` if the familly doesn't use a CRP word.
+pub fn crp_offset(family: Family) -> Option<usize> {
+    match family {
+        Family::Lpc2000 => Some(ARM7_CRP_OFFSET),
+        Family::Lpc800
+        | Family::Lpc1100
+        | Family::Lpc1200
+        | Family::Lpc1300
+        | Family::Lpc1500
+        | Family::Lpc1700
+        | Family::Lpc1800
+        | Family::Lpc4000
+        | Family::Lpc4300 => Some(CORTEX_M_CRP_OFFSET),
+        Family::Lpc2900 | Family::Lpc3000 | Family::Lpc5400 | Family::Lpc5500 => None,
+    }
+}
+
+/// Read the CRP level of the given image.
+///
+/// Returns `None` if the image is too short to contain the CRP word.
+pub fn read_crp(image: &[u8], offset: usize) -> Option<CrpLevel> {
+    let word = image.get(offset..offset + 4)?;
+
+    Some(CrpLevel::from_word(u32::from_le_bytes([
+        word[0], word[1], word[2], word[3],
+    ])))
+}
+
+/// Returns true if the CRP word location holds a CRP level or padding, and not code or data.
+pub fn is_crp_location_free(word: u32) -> bool {
+    word == 0 || word == 0xFFFF_FFFF || CrpLevel::from_word(word).is_protected()
+}
+
+/// Write the CRP level in the given image.
+///
+/// The location must not be used by code or data already.
+pub fn write_crp(image: &mut [u8], offset: usize, level: CrpLevel) -> Result<()> {
+    let image_size = image.len();
+    let word = image
+        .get_mut(offset..offset + 4)
+        .ok_or(Error::ImageTooShort {
+            required: offset + 4,
+            actual: image_size,
+        })?;
+    let old_word = u32::from_le_bytes([word[0], word[1], word[2], word[3]]);
+
+    if !is_crp_location_free(old_word) {
+        return Err(Error::CrpLocationUsed(old_word));
+    }
+
+    word.copy_from_slice(&level.word().to_le_bytes());
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_crp_words() {
+        for (word, level) in [
+            (0x4E69_7370, CrpLevel::NoIsp),
+            (0x1234_5678, CrpLevel::Crp1),
+            (0x8765_4321, CrpLevel::Crp2),
+            (0x4321_8765, CrpLevel::Crp3),
+            (0xFFFF_FFFF, CrpLevel::None),
+            (0x2000_8000, CrpLevel::None),
+        ] {
+            assert_eq!(CrpLevel::from_word(word), level);
+        }
+
+        let mut image = [0xFF; 0x300];
+        image[0x2FC..].copy_from_slice(&0x4321_8765u32.to_le_bytes());
+
+        assert_eq!(read_crp(&image, CORTEX_M_CRP_OFFSET), Some(CrpLevel::Crp3));
+        assert_eq!(read_crp(&image, ARM7_CRP_OFFSET), Some(CrpLevel::None));
+        assert_eq!(read_crp(&image[..0x2FF], CORTEX_M_CRP_OFFSET), None);
+    }
+
+    #[test]
+    fn write_each_level() {
+        for offset in [CORTEX_M_CRP_OFFSET, ARM7_CRP_OFFSET] {
+            for level in CrpLevel::ALL.iter().copied() {
+                let mut image = [0; 0x300];
+
+                write_crp(&mut image, offset, level).unwrap();
+                assert_eq!(image[offset..offset + 4], level.word().to_le_bytes());
+                assert_eq!(read_crp(&image, offset), Some(level));
+
+                // The other bytes are left untouched.
+                image[offset..offset + 4].fill(0);
+                assert_eq!(image, [0; 0x300]);
+            }
+        }
+
+        // A CRP level can be replaced, or cleared.
+        let mut image = [0; 0x300];
+        write_crp(&mut image, CORTEX_M_CRP_OFFSET, CrpLevel::Crp3).unwrap();
+        write_crp(&mut image, CORTEX_M_CRP_OFFSET, CrpLevel::None).unwrap();
+        assert_eq!(read_crp(&image, CORTEX_M_CRP_OFFSET), Some(CrpLevel::None));
+    }
+
+    #[test]
+    fn location_used_by_code() {
+        let mut image = [0; 0x300];
+        image[0x1FC..0x200].copy_from_slice(&0xE59F_F018u32.to_le_bytes());
+
+        assert!(matches!(
+            write_crp(&mut image, ARM7_CRP_OFFSET, CrpLevel::Crp1),
+            Err(Error::CrpLocationUsed(0xE59F_F018))
+        ));
+        assert_eq!(image[0x1FC..0x200], 0xE59F_F018u32.to_le_bytes());
+
+        assert!(matches!(
+            write_crp(&mut image[..0x2FE], CORTEX_M_CRP_OFFSET, CrpLevel::Crp1),
+            Err(Error::ImageTooShort {
+                required: 0x300,
+                actual: 0x2FE
+            })
+        ));
+    }
+
+    #[test]
+    fn crp_offsets() {
+        assert_eq!(crp_offset(Family::Lpc2000), Some(0x1FC));
+        assert_eq!(crp_offset(Family::Lpc1700), Some(0x2FC));
+        assert_eq!(crp_offset(Family::Lpc5500), None);
+        assert_eq!(CrpLevel::from_name("no_isp"), Some(CrpLevel::NoIsp));
+    }
+}
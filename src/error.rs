@@ -0,0 +1,140 @@
+//! Error type of this crate.
+
+use crate::vectors::VectorIssue;
+use core::fmt;
+
+/// Errors that can happen while handling an image.
+#[derive(Debug)]
+pub enum Error {
+    /// An I/O error happened while accessing the image.
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+    /// The image file is malformed.
+    #[cfg(feature = "std")]
+    InvalidImage(String),
+    /// The requested operation doesn't apply to the processor or the image.
+    #[cfg(feature = "std")]
+    Usage(String),
+    /// The image is too short to contain the data needed.
+    ImageTooShort {
+        /// The size in bytes needed.
+        required: usize,
+        /// The actual size in bytes of the image.
+        actual: usize,
+    },
+    /// The BootROM of the processor familly doesn't validate a checksum.
+    UnsupportedFamily(&'static str),
+    /// The processor isn't known.
+    UnknownProcessor,
+    /// The checksum stored in the image doesn't match the computed one.
+    ChecksumMismatch {
+        /// The computed checksum.
+        expected: u32,
+        /// The checksum stored in the image.
+        found: u32,
+    },
+    /// The CRC32 stored in the image header doesn't match the computed one.
+    CrcMismatch {
+        /// The computed CRC32.
+        expected: u32,
+        /// The CRC32 stored in the image.
+        found: u32,
+    },
+    /// The image enables CRP3 and patching it wasn't acknowledged.
+    PermanentCrp,
+    /// The CRP word location holds code or data.
+    CrpLocationUsed(u32),
+    /// The vector table doesn't match the memory map of the part.
+    InvalidVectorTable(VectorIssue),
+    /// The boot image header is malformed or doesn't match the image.
+    InvalidBootHeader(&'static str),
+    /// The PRINCE regions or keys don't match the image.
+    #[cfg(feature = "secure")]
+    InvalidPrince(&'static str),
+    /// The certificate block or signature of a signed image is invalid.
+    #[cfg(feature = "secure")]
+    InvalidSignature(String),
+    /// The protected flash region page or its description is invalid.
+    #[cfg(feature = "secure")]
+    InvalidProtectedPage(String),
+    /// The secure binary file is invalid.
+    #[cfg(feature = "secure")]
+    InvalidSecureBinary(String),
+}
+
+/// Result type of this crate.
+pub type Result<T> = core::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "std")]
+            Error::Io(error) => write!(f, "I/O error: {}", error),
+            #[cfg(feature = "std")]
+            Error::InvalidImage(message) => write!(f, "{}", message),
+            #[cfg(feature = "std")]
+            Error::Usage(message) => write!(f, "{}", message),
+            Error::ImageTooShort { required, actual } => write!(
+                f,
+                "Image too short: {} bytes, at least {} bytes needed",
+                actual, required
+            ),
+            Error::UnsupportedFamily(cpu_family) => {
+                write!(f, "Checksum not supported for {}", cpu_family)
+            }
+            Error::UnknownProcessor => write!(f, "Unknown processor"),
+            Error::ChecksumMismatch { expected, found } => write!(
+                f,
+                "Checksum mismatch: expected 0x{:08x}, found 0x{:08x}",
+                expected, found
+            ),
+            Error::CrcMismatch { expected, found } => write!(
+                f,
+                "CRC mismatch: expected 0x{:08x}, found 0x{:08x}",
+                expected, found
+            ),
+            Error::PermanentCrp => write!(
+                f,
+                "Image enables CRP3, which permanently disables ISP and debug access"
+            ),
+            Error::CrpLocationUsed(word) => write!(
+                f,
+                "CRP word location already used by code or data (0x{:08x})",
+                word
+            ),
+            Error::InvalidVectorTable(issue) => write!(f, "Invalid vector table: {}", issue),
+            Error::InvalidBootHeader(message) => {
+                write!(f, "Invalid boot image header: {}", message)
+            }
+            #[cfg(feature = "secure")]
+            Error::InvalidPrince(message) => write!(f, "Invalid PRINCE configuration: {}", message),
+            #[cfg(feature = "secure")]
+            Error::InvalidSignature(message) => write!(f, "Invalid signature: {}", message),
+            #[cfg(feature = "secure")]
+            Error::InvalidProtectedPage(message) => {
+                write!(f, "Invalid protected flash page: {}", message)
+            }
+            #[cfg(feature = "secure")]
+            Error::InvalidSecureBinary(message) => {
+                write!(f, "Invalid secure binary file: {}", message)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error::Io(error)
+    }
+}
@@ -0,0 +1,353 @@
+//! ELF file format.
+//!
+//! Only 32 bits little endian files are supported. The image is patched directly in the file
+//! content, leaving symbols and debug sections untouched.
+
+use super::Image;
+use crate::{Error, Result};
+use std::convert::TryInto;
+use std::io::Write;
+
+/// ELF file magic.
+const ELF_MAGIC: &[u8] = b"\x7fELF";
+
+/// 32 bits ELF class.
+const ELFCLASS32: u8 = 1;
+
+/// Little endian ELF data encoding.
+const ELFDATA2LSB: u8 = 1;
+
+/// Loadable segment program header type.
+const PT_LOAD: u32 = 1;
+
+/// Section with program defined content.
+const SHT_PROGBITS: u32 = 1;
+
+/// Section occupying memory during execution.
+const SHF_ALLOC: u32 = 2;
+
+/// A part of the file content loaded in memory.
+#[derive(Debug)]
+pub struct Region {
+    /// The physical address of the region.
+    pub address: u32,
+    /// The offset of the region in the file.
+    pub offset: usize,
+    /// The size of the region in the file.
+    pub size: usize,
+}
+
+/// An ELF file.
+#[derive(Debug)]
+pub struct Elf {
+    /// The content of the file.
+    data: Vec<u8>,
+    /// The regions of the file loaded in memory.
+    pub regions: Vec<Region>,
+    /// The address of the first byte of the image.
+    base_address: u32,
+}
+
+fn invalid_data(message: &str) -> Error {
+    Error::InvalidImage(format!("Invalid ELF file: {}", message))
+}
+
+impl Elf {
+    /// Returns true if the given file content looks like an ELF file.
+    pub fn is_elf(data: &[u8]) -> bool {
+        data.starts_with(ELF_MAGIC)
+    }
+
+    /// Parse an ELF file.
+    pub fn parse(data: Vec<u8>) -> Result<Self> {
+        if data.len() < 0x34 || !Self::is_elf(&data) {
+            return Err(invalid_data("truncated header"));
+        }
+
+        if data[4] != ELFCLASS32 || data[5] != ELFDATA2LSB {
+            return Err(invalid_data(
+                "only 32 bits little endian files are supported",
+            ));
+        }
+
+        let mut elf = Elf {
+            data,
+            regions: Vec::new(),
+            base_address: 0,
+        };
+
+        elf.regions = elf.segments()?;
+
+        if elf.regions.is_empty() {
+            elf.regions = elf.sections()?;
+        }
+
+        elf.base_address = super::lowest_address(&elf.chunks());
+
+        Ok(elf)
+    }
+
+    fn read_u16(&self, offset: usize) -> Result<u16> {
+        self.data
+            .get(offset..offset + 2)
+            .map(|value| u16::from_le_bytes(value.try_into().unwrap()))
+            .ok_or_else(|| invalid_data("truncated file"))
+    }
+
+    fn read_u32(&self, offset: usize) -> Result<u32> {
+        self.data
+            .get(offset..offset + 4)
+            .map(|value| u32::from_le_bytes(value.try_into().unwrap()))
+            .ok_or_else(|| invalid_data("truncated file"))
+    }
+
+    /// Get the offset of each entry of a header table.
+    fn table(&self, offset: usize, entry_size: usize, count: usize) -> Vec<usize> {
+        (0..count).map(|i| offset + i * entry_size).collect()
+    }
+
+    /// Get a region, checking it's inside the file.
+    fn region(&self, address: u32, offset: u32, size: u32) -> Result<Region> {
+        let region = Region {
+            address,
+            offset: offset as usize,
+            size: size as usize,
+        };
+
+        if region.offset + region.size > self.data.len() {
+            return Err(invalid_data("truncated file"));
+        }
+
+        Ok(region)
+    }
+
+    /// Get the loadable segments with content in the file, using their physical address.
+    fn segments(&self) -> Result<Vec<Region>> {
+        let table = self.table(
+            self.read_u32(0x1c)? as usize,
+            self.read_u16(0x2a)? as usize,
+            self.read_u16(0x2c)? as usize,
+        );
+        let mut result = Vec::new();
+
+        for header in table {
+            let segment_type = self.read_u32(header)?;
+            let offset = self.read_u32(header + 0x4)?;
+            let physical_address = self.read_u32(header + 0xc)?;
+            let file_size = self.read_u32(header + 0x10)?;
+
+            if segment_type == PT_LOAD && file_size != 0 {
+                result.push(self.region(physical_address, offset, file_size)?);
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Get the allocated sections with content in the file.
+    fn sections(&self) -> Result<Vec<Region>> {
+        let table = self.table(
+            self.read_u32(0x20)? as usize,
+            self.read_u16(0x2e)? as usize,
+            self.read_u16(0x30)? as usize,
+        );
+        let mut result = Vec::new();
+
+        for header in table {
+            let section_type = self.read_u32(header + 0x4)?;
+            let flags = self.read_u32(header + 0x8)?;
+            let address = self.read_u32(header + 0xc)?;
+            let offset = self.read_u32(header + 0x10)?;
+            let size = self.read_u32(header + 0x14)?;
+
+            if section_type == SHT_PROGBITS && flags & SHF_ALLOC != 0 && size != 0 {
+                result.push(self.region(address, offset, size)?);
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Get the address and size of every region, with the index of the region.
+    fn chunks(&self) -> Vec<(usize, u32, usize)> {
+        self.regions
+            .iter()
+            .enumerate()
+            .map(|(i, region)| (i, region.address, region.size))
+            .collect()
+    }
+}
+
+impl Image for Elf {
+    fn format_name(&self) -> &'static str {
+        "ELF"
+    }
+
+    fn base_address(&self) -> u32 {
+        self.base_address
+    }
+
+    fn size(&self) -> usize {
+        super::end_offset(&self.chunks(), self.base_address)
+    }
+
+    fn read(&self, offset: usize, buffer: &mut [u8]) -> Result<()> {
+        super::covering_overlaps(self, &self.chunks(), offset, buffer.len())?;
+
+        self.read_filled(offset, buffer)
+    }
+
+    fn read_filled(&self, offset: usize, buffer: &mut [u8]) -> Result<()> {
+        let chunks = self.chunks();
+        buffer.fill(super::ERASED_BYTE);
+
+        for (i, region_start, range) in super::overlaps(self, &chunks, offset, buffer.len()) {
+            let start = self.regions[i].offset + region_start;
+            buffer[range.clone()].copy_from_slice(&self.data[start..start + range.len()]);
+        }
+
+        Ok(())
+    }
+
+    fn write(&mut self, offset: usize, data: &[u8]) -> Result<()> {
+        let chunks = self.chunks();
+
+        for (i, region_start, range) in super::covering_overlaps(self, &chunks, offset, data.len())?
+        {
+            let start = self.regions[i].offset + region_start;
+            self.data[start..start + range.len()].copy_from_slice(&data[range]);
+        }
+
+        Ok(())
+    }
+
+    fn set_flash_base(&mut self, addresses: &[u32]) -> Result<()> {
+        self.base_address = super::locate(self.format_name(), &self.chunks(), addresses)?;
+
+        Ok(())
+    }
+
+    fn save(&self, output: &mut dyn Write) -> std::io::Result<()> {
+        output.write_all(&self.data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Offset of the loaded content in the test files.
+    const TEXT_OFFSET: usize = 0x60;
+
+    /// Offset of the content not loaded in memory, such as symbols and debug information.
+    const DEBUG_OFFSET: usize = 0x70;
+
+    /// Offset of the section header table.
+    const SECTION_TABLE_OFFSET: usize = 0x80;
+
+    fn put(data: &mut [u8], offset: usize, values: &[u32]) {
+        for (i, value) in values.iter().enumerate() {
+            data[offset + i * 4..offset + i * 4 + 4].copy_from_slice(&value.to_le_bytes());
+        }
+    }
+
+    /// Build an ELF file with 16 bytes of code loaded at 0x1A000000 and 8 bytes of debug
+    /// information, with or without the program header of the code.
+    fn elf(program_header: bool) -> Vec<u8> {
+        let mut data = vec![0; SECTION_TABLE_OFFSET + 3 * 0x28];
+
+        data[..7].copy_from_slice(b"\x7fELF\x01\x01\x01");
+        put(&mut data, 0x10, &[0x0028_0002, 1, 0x1A00_0000, 0x34]);
+        put(
+            &mut data,
+            0x20,
+            &[SECTION_TABLE_OFFSET as u32, 0, 0x0020_0034],
+        );
+        data[0x2c] = program_header as u8;
+        put(&mut data, 0x2e, &[0x0003_0028]);
+
+        // The code is linked in RAM, loaded from flash.
+        put(
+            &mut data,
+            0x34,
+            &[
+                PT_LOAD,
+                TEXT_OFFSET as u32,
+                0x1000_0000,
+                0x1A00_0000,
+                16,
+                16,
+                5,
+                4,
+            ],
+        );
+
+        for (i, value) in data[TEXT_OFFSET..DEBUG_OFFSET + 8].iter_mut().enumerate() {
+            *value = i as u8;
+        }
+
+        put(
+            &mut data,
+            SECTION_TABLE_OFFSET + 0x28,
+            &[
+                0,
+                SHT_PROGBITS,
+                SHF_ALLOC | 4,
+                0x1A00_0000,
+                TEXT_OFFSET as u32,
+                16,
+            ],
+        );
+        put(
+            &mut data,
+            SECTION_TABLE_OFFSET + 0x50,
+            &[0, SHT_PROGBITS, 0, 0, DEBUG_OFFSET as u32, 8],
+        );
+
+        data
+    }
+
+    #[test]
+    fn loadable_segments() {
+        let data = elf(true);
+        let mut image = Elf::parse(data.clone()).unwrap();
+
+        assert_eq!(image.regions.len(), 1);
+        assert_eq!(image.base_address(), 0x1A00_0000);
+        assert_eq!(image.size(), 16);
+        assert_eq!(image.read_vec(4, 4).unwrap(), [4, 5, 6, 7]);
+
+        // Only the patched bytes of the file change.
+        image.write(4, &[0xAA; 4]).unwrap();
+
+        let mut output = Vec::new();
+        image.save(&mut output).unwrap();
+
+        let mut expected = data;
+        expected[TEXT_OFFSET + 4..TEXT_OFFSET + 8].copy_from_slice(&[0xAA; 4]);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn allocated_sections() {
+        let image = Elf::parse(elf(false)).unwrap();
+
+        // The debug section isn't loaded in memory.
+        assert_eq!(image.regions.len(), 1);
+        assert_eq!(image.base_address(), 0x1A00_0000);
+        assert_eq!(image.size(), 16);
+        assert!(matches!(image.read_vec(16, 1), Err(Error::InvalidImage(_))));
+    }
+
+    #[test]
+    fn invalid_files() {
+        let mut data = elf(true);
+        data[4] = 2;
+        assert!(matches!(Elf::parse(data), Err(Error::InvalidImage(_))));
+
+        assert!(matches!(
+            Elf::parse(elf(true)[..0x34].to_vec()),
+            Err(Error::InvalidImage(_))
+        ));
+    }
+}
@@ -0,0 +1,366 @@
+//! Intel HEX file format.
+
+use super::Image;
+use crate::{Error, Result};
+use std::io::Write;
+
+/// The type of an Intel HEX record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordType {
+    /// Data bytes.
+    Data,
+    /// End of file marker.
+    EndOfFile,
+    /// Segment base address (bits 4 to 19) of the following data records.
+    ExtendedSegmentAddress,
+    /// CS:IP entry point.
+    StartSegmentAddress,
+    /// Upper 16 bits of the address of the following data records.
+    ExtendedLinearAddress,
+    /// 32 bits entry point.
+    StartLinearAddress,
+}
+
+impl RecordType {
+    fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(RecordType::Data),
+            1 => Some(RecordType::EndOfFile),
+            2 => Some(RecordType::ExtendedSegmentAddress),
+            3 => Some(RecordType::StartSegmentAddress),
+            4 => Some(RecordType::ExtendedLinearAddress),
+            5 => Some(RecordType::StartLinearAddress),
+            _ => None,
+        }
+    }
+
+    fn to_u8(self) -> u8 {
+        match self {
+            RecordType::Data => 0,
+            RecordType::EndOfFile => 1,
+            RecordType::ExtendedSegmentAddress => 2,
+            RecordType::StartSegmentAddress => 3,
+            RecordType::ExtendedLinearAddress => 4,
+            RecordType::StartLinearAddress => 5,
+        }
+    }
+}
+
+/// A single Intel HEX record.
+#[derive(Debug)]
+pub struct Record {
+    /// The type of the record.
+    pub record_type: RecordType,
+    /// The 16 bits address field.
+    pub address: u16,
+    /// The payload of the record.
+    pub data: Vec<u8>,
+}
+
+impl Record {
+    /// Compute the checksum byte of the record.
+    pub fn checksum(&self) -> u8 {
+        let sum = self
+            .data
+            .iter()
+            .chain(&self.address.to_be_bytes())
+            .chain(&[self.data.len() as u8, self.record_type.to_u8()])
+            .fold(0u8, |sum, value| sum.wrapping_add(*value));
+
+        0u8.wrapping_sub(sum)
+    }
+}
+
+/// An Intel HEX file, keeping the original record layout.
+#[derive(Debug)]
+pub struct IntelHex {
+    /// The records of the file.
+    pub records: Vec<Record>,
+    /// The line ending used by the file.
+    line_ending: &'static str,
+    /// Whether the file uses lowercase hexadecimal digits.
+    lowercase: bool,
+    /// The address of the first byte of the image.
+    base_address: u32,
+}
+
+fn invalid_data(line: usize, message: &str) -> Error {
+    Error::InvalidImage(format!(
+        "Invalid Intel HEX record at line {}: {}",
+        line, message
+    ))
+}
+
+impl IntelHex {
+    /// Returns true if the given file content looks like an Intel HEX file.
+    pub fn is_intel_hex(data: &[u8]) -> bool {
+        data.iter()
+            .find(|value| !value.is_ascii_whitespace())
+            .is_some_and(|value| *value == b':')
+    }
+
+    /// Parse an Intel HEX file.
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let text = std::str::from_utf8(data)
+            .map_err(|_| Error::InvalidImage("Intel HEX file isn't valid ASCII".to_string()))?;
+
+        let line_ending = if text.contains("\r\n") { "\r\n" } else { "\n" };
+        let lowercase = text.chars().any(|value| value.is_ascii_lowercase());
+        let mut records = Vec::new();
+
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            let record = Self::parse_record(i + 1, line)?;
+            let is_end_of_file = record.record_type == RecordType::EndOfFile;
+
+            records.push(record);
+
+            if is_end_of_file {
+                break;
+            }
+        }
+
+        let mut image = IntelHex {
+            records,
+            line_ending,
+            lowercase,
+            base_address: 0,
+        };
+        image.base_address = super::lowest_address(&image.chunks());
+
+        Ok(image)
+    }
+
+    fn parse_record(line_number: usize, line: &str) -> Result<Record> {
+        if !line.is_ascii()
+            || !line.starts_with(':')
+            || line.len() < 11
+            || line.len().is_multiple_of(2)
+        {
+            return Err(invalid_data(line_number, "malformed record"));
+        }
+
+        let bytes = (1..line.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&line[i..i + 2], 16))
+            .collect::<core::result::Result<Vec<u8>, _>>()
+            .map_err(|_| invalid_data(line_number, "invalid hexadecimal digit"))?;
+
+        if bytes[0] as usize + 5 != bytes.len() {
+            return Err(invalid_data(line_number, "length mismatch"));
+        }
+
+        let record_type = RecordType::from_u8(bytes[3])
+            .ok_or_else(|| invalid_data(line_number, "unknown record type"))?;
+
+        let record = Record {
+            record_type,
+            address: u16::from_be_bytes([bytes[1], bytes[2]]),
+            data: bytes[4..bytes.len() - 1].to_vec(),
+        };
+
+        if record.checksum() != bytes[bytes.len() - 1] {
+            return Err(invalid_data(line_number, "checksum mismatch"));
+        }
+
+        Ok(record)
+    }
+
+    /// Get the absolute address of every data record, with the index of the record.
+    pub fn data_records(&self) -> Vec<(usize, u32)> {
+        let mut result = Vec::new();
+        let mut upper_address = 0u32;
+
+        for (i, record) in self.records.iter().enumerate() {
+            match record.record_type {
+                RecordType::ExtendedSegmentAddress if record.data.len() == 2 => {
+                    upper_address =
+                        u32::from(u16::from_be_bytes([record.data[0], record.data[1]])) << 4;
+                }
+                RecordType::ExtendedLinearAddress if record.data.len() == 2 => {
+                    upper_address =
+                        u32::from(u16::from_be_bytes([record.data[0], record.data[1]])) << 16;
+                }
+                RecordType::Data => {
+                    result.push((i, upper_address.wrapping_add(u32::from(record.address))));
+                }
+                _ => {}
+            }
+        }
+
+        result
+    }
+
+    /// Get the address and size of every data record, with the index of the record.
+    fn chunks(&self) -> Vec<(usize, u32, usize)> {
+        self.data_records()
+            .into_iter()
+            .map(|(i, address)| (i, address, self.records[i].data.len()))
+            .collect()
+    }
+}
+
+impl Image for IntelHex {
+    fn format_name(&self) -> &'static str {
+        "Intel HEX"
+    }
+
+    fn base_address(&self) -> u32 {
+        self.base_address
+    }
+
+    fn size(&self) -> usize {
+        super::end_offset(&self.chunks(), self.base_address)
+    }
+
+    fn read(&self, offset: usize, buffer: &mut [u8]) -> Result<()> {
+        super::covering_overlaps(self, &self.chunks(), offset, buffer.len())?;
+
+        self.read_filled(offset, buffer)
+    }
+
+    fn read_filled(&self, offset: usize, buffer: &mut [u8]) -> Result<()> {
+        let chunks = self.chunks();
+        buffer.fill(super::ERASED_BYTE);
+
+        for (i, record_start, range) in super::overlaps(self, &chunks, offset, buffer.len()) {
+            let record_end = record_start + range.len();
+            buffer[range].copy_from_slice(&self.records[i].data[record_start..record_end]);
+        }
+
+        Ok(())
+    }
+
+    fn write(&mut self, offset: usize, data: &[u8]) -> Result<()> {
+        let chunks = self.chunks();
+
+        for (i, record_start, range) in super::covering_overlaps(self, &chunks, offset, data.len())?
+        {
+            let record_end = record_start + range.len();
+            self.records[i].data[record_start..record_end].copy_from_slice(&data[range]);
+        }
+
+        Ok(())
+    }
+
+    fn set_flash_base(&mut self, addresses: &[u32]) -> Result<()> {
+        self.base_address = super::locate(self.format_name(), &self.chunks(), addresses)?;
+
+        Ok(())
+    }
+
+    fn save(&self, output: &mut dyn Write) -> std::io::Result<()> {
+        for record in &self.records {
+            let mut line = format!(
+                ":{:02X}{:04X}{:02X}",
+                record.data.len(),
+                record.address,
+                record.record_type.to_u8()
+            );
+
+            for value in &record.data {
+                line.push_str(&format!("{:02X}", value));
+            }
+
+            line.push_str(&format!("{:02X}", record.checksum()));
+
+            if self.lowercase {
+                line.make_ascii_lowercase();
+            }
+
+            write!(output, "{}{}", line, self.line_ending)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Image of 20 bytes at 0x1A000000, with a 32 bits entry point.
+    const IMAGE: &str = ":020000041A00E0\n\
+                         :10000000000102030405060708090A0B0C0D0E0F78\n\
+                         :0400100010111213A6\n\
+                         :040000051A0000C11C\n\
+                         :00000001FF\n";
+
+    fn save(image: &IntelHex) -> String {
+        let mut output = Vec::new();
+        image.save(&mut output).unwrap();
+
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut image = IntelHex::parse(IMAGE.as_bytes()).unwrap();
+        assert_eq!(save(&image), IMAGE);
+
+        // Patching bytes across two records updates their checksums.
+        image.write(0xE, &[0xAA, 0xBB, 0xCC]).unwrap();
+        let patched = save(&image);
+        assert!(patched.contains(":10000000000102030405060708090A0B0C0DAABB30\n"));
+        assert!(patched.contains(":04001000CC111213EA\n"));
+
+        let parsed = IntelHex::parse(patched.as_bytes()).unwrap();
+        assert_eq!(parsed.read_vec(0xE, 3).unwrap(), [0xAA, 0xBB, 0xCC]);
+    }
+
+    #[test]
+    fn extended_linear_address() {
+        let image = IntelHex::parse(IMAGE.as_bytes()).unwrap();
+
+        assert_eq!(image.base_address(), 0x1A00_0000);
+        assert_eq!(image.size(), 20);
+        assert_eq!(image.read_vec(0x10, 4).unwrap(), [0x10, 0x11, 0x12, 0x13]);
+        assert_eq!(image.data_records(), [(1, 0x1A00_0000), (2, 0x1A00_0010)]);
+        assert!(matches!(
+            image.read_vec(0x10, 5),
+            Err(Error::InvalidImage(_))
+        ));
+    }
+
+    #[test]
+    fn lowercase_crlf() {
+        let text = IMAGE.to_ascii_lowercase().replace('\n', "\r\n");
+        let mut image = IntelHex::parse(text.as_bytes()).unwrap();
+        assert_eq!(save(&image), text);
+
+        image.write(0, &[0xAB]).unwrap();
+        assert!(save(&image).contains(":10000000ab0102030405060708090a0b0c0d0e0fcd\r\n"));
+    }
+
+    #[test]
+    fn invalid_records() {
+        for (text, message) in [
+            (
+                ":10000000000102030405060708090A0B0C0D0E0F79\n",
+                "checksum mismatch",
+            ),
+            (
+                ":11000000000102030405060708090A0B0C0D0E0F78\n",
+                "length mismatch",
+            ),
+            (":0400000000010G0300\n", "invalid hexadecimal digit"),
+            (":00000006FA\n", "unknown record type"),
+            (":000000\n", "malformed record"),
+        ] {
+            match IntelHex::parse(text.as_bytes()) {
+                Err(Error::InvalidImage(error)) => {
+                    assert_eq!(
+                        error,
+                        format!("Invalid Intel HEX record at line 1: {}", message)
+                    )
+                }
+                result => panic!("{:?} parsed as {:?}", text, result),
+            }
+        }
+    }
+}
@@ -0,0 +1,350 @@
+//! Firmware image file formats.
+//!
+//! Every format exposes the image as a contiguous memory area starting at its base address,
+//! so the checksum can be computed and patched without caring about the file layout.
+//! Formats with addresses start at their lowest address, until the flash base address of the
+//! part is located with [`Image::set_flash_base`]. Their gaps between the data read as erased
+//! flash with [`Image::read_filled`], and their data in other memories isn't part of the image.
+
+pub mod elf;
+pub mod ihex;
+pub mod srec;
+
+use crate::{Error, Result};
+use std::io::Write;
+use std::ops::Range;
+
+pub use elf::Elf;
+pub use ihex::IntelHex;
+pub use srec::SRecord;
+
+/// Value of the bytes of erased flash.
+pub const ERASED_BYTE: u8 = 0xFF;
+
+/// Size of the memory areas of the LPC memory maps, each holding a single flash bank, external
+/// memory or RAM.
+const MEMORY_AREA_SIZE: u64 = 0x100_0000;
+
+/// A firmware image loaded in memory.
+pub trait Image {
+    /// The name of the file format.
+    fn format_name(&self) -> &'static str;
+
+    /// The address of the first byte of the image.
+    fn base_address(&self) -> u32;
+
+    /// The size in bytes from the base address to the end of the last data byte in the memory
+    /// area of the base address.
+    fn size(&self) -> usize;
+
+    /// Read bytes at the given offset from the image base.
+    fn read(&self, offset: usize, buffer: &mut [u8]) -> Result<()>;
+
+    /// Read bytes at the given offset from the image base, the gaps between the data reading as
+    /// erased flash.
+    ///
+    /// Raw binary images don't have gaps.
+    fn read_filled(&self, offset: usize, buffer: &mut [u8]) -> Result<()> {
+        self.read(offset, buffer)
+    }
+
+    /// Overwrite bytes at the given offset from the image base.
+    fn write(&mut self, offset: usize, data: &[u8]) -> Result<()>;
+
+    /// Serialize the image back to its original format.
+    fn save(&self, output: &mut dyn Write) -> std::io::Result<()>;
+
+    /// Start the image at the first of the given flash base addresses it contains data for,
+    /// where the boot ROM reads the vector table.
+    ///
+    /// Fails if the image doesn't contain any of them. Raw binary images don't have addresses
+    /// and are always assumed to start at the flash base.
+    fn set_flash_base(&mut self, _addresses: &[u32]) -> Result<()> {
+        Ok(())
+    }
+
+    /// Read `size` bytes at the given offset from the image base.
+    fn read_vec(&self, offset: usize, size: usize) -> Result<Vec<u8>> {
+        let mut buffer = vec![0; size];
+        self.read(offset, &mut buffer)?;
+
+        Ok(buffer)
+    }
+
+    /// Read the whole image, the gaps between the data reading as erased flash.
+    fn read_all(&self) -> Result<Vec<u8>> {
+        let mut buffer = vec![ERASED_BYTE; self.size()];
+        self.read_filled(0, &mut buffer)?;
+
+        Ok(buffer)
+    }
+}
+
+/// A raw binary image.
+#[derive(Debug)]
+pub struct BinaryImage {
+    /// The content of the image.
+    pub data: Vec<u8>,
+}
+
+impl BinaryImage {
+    /// Get the byte range at the given offset, checking it's inside the image.
+    fn range(&self, offset: usize, size: usize) -> Result<std::ops::Range<usize>> {
+        if offset + size > self.data.len() {
+            return Err(Error::ImageTooShort {
+                required: offset + size,
+                actual: self.data.len(),
+            });
+        }
+
+        Ok(offset..offset + size)
+    }
+}
+
+impl Image for BinaryImage {
+    fn format_name(&self) -> &'static str {
+        "binary"
+    }
+
+    fn base_address(&self) -> u32 {
+        0
+    }
+
+    fn size(&self) -> usize {
+        self.data.len()
+    }
+
+    fn read(&self, offset: usize, buffer: &mut [u8]) -> Result<()> {
+        let range = self.range(offset, buffer.len())?;
+        buffer.copy_from_slice(&self.data[range]);
+
+        Ok(())
+    }
+
+    fn write(&mut self, offset: usize, data: &[u8]) -> Result<()> {
+        let range = self.range(offset, data.len())?;
+        self.data[range].copy_from_slice(data);
+
+        Ok(())
+    }
+
+    fn save(&self, output: &mut dyn Write) -> std::io::Result<()> {
+        output.write_all(&self.data)
+    }
+}
+
+/// Get the lowest address of the given non empty chunks of data.
+///
+/// Chunks are described by an index, their address and their size.
+fn lowest_address(chunks: &[(usize, u32, usize)]) -> u32 {
+    chunks
+        .iter()
+        .filter(|(_, _, size)| *size != 0)
+        .map(|(_, address, _)| *address)
+        .min()
+        .unwrap_or(0)
+}
+
+/// Get the first of the given addresses covered by the given chunks of data.
+fn locate(format_name: &str, chunks: &[(usize, u32, usize)], addresses: &[u32]) -> Result<u32> {
+    addresses
+        .iter()
+        .copied()
+        .find(|address| {
+            chunks.iter().any(|(_, chunk_address, size)| {
+                address
+                    .checked_sub(*chunk_address)
+                    .is_some_and(|offset| (offset as usize) < *size)
+            })
+        })
+        .ok_or_else(|| {
+            Error::InvalidImage(format!(
+                "{} file doesn't contain data at the flash base address 0x{:x}",
+                format_name,
+                addresses.first().copied().unwrap_or(0)
+            ))
+        })
+}
+
+/// Get the size from the base address to the end of the given chunks of data.
+///
+/// Chunks located below the base address or in another memory area, such as the second flash
+/// bank of the LPC1800/LPC4300, aren't part of the image.
+fn end_offset(chunks: &[(usize, u32, usize)], base_address: u32) -> usize {
+    let base_address = u64::from(base_address);
+    let area_end = (base_address / MEMORY_AREA_SIZE + 1) * MEMORY_AREA_SIZE;
+
+    chunks
+        .iter()
+        .filter(|(_, address, _)| u64::from(*address) < area_end)
+        .map(|(_, address, size)| {
+            std::cmp::min(u64::from(*address) + *size as u64, area_end).saturating_sub(base_address)
+                as usize
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// Get every part of the given chunks of data overlapping a range relative to the image base.
+///
+/// Returns the index of the chunk, the start in the chunk data and the part of the range covered.
+fn overlaps(
+    image: &dyn Image,
+    chunks: &[(usize, u32, usize)],
+    offset: usize,
+    size: usize,
+) -> Vec<(usize, usize, Range<usize>)> {
+    let range_start = u64::from(image.base_address()) + offset as u64;
+    let range_end = range_start + size as u64;
+    let mut result = Vec::new();
+
+    for (i, address, chunk_size) in chunks {
+        let chunk_start = u64::from(*address);
+        let chunk_end = chunk_start + *chunk_size as u64;
+
+        let overlap_start = std::cmp::max(chunk_start, range_start);
+        let overlap_end = std::cmp::min(chunk_end, range_end);
+
+        if overlap_start < overlap_end {
+            let start = (overlap_start - range_start) as usize;
+            let end = (overlap_end - range_start) as usize;
+
+            result.push((*i, (overlap_start - chunk_start) as usize, start..end));
+        }
+    }
+
+    result
+}
+
+/// Get every part of the given chunks of data covering a range relative to the image base, as
+/// [`overlaps`].
+///
+/// Fails if a part of the range isn't covered by any chunk.
+fn covering_overlaps(
+    image: &dyn Image,
+    chunks: &[(usize, u32, usize)],
+    offset: usize,
+    size: usize,
+) -> Result<Vec<(usize, usize, Range<usize>)>> {
+    let result = overlaps(image, chunks, offset, size);
+    let mut covered = vec![false; size];
+
+    for (_, _, range) in &result {
+        for value in &mut covered[range.clone()] {
+            *value = true;
+        }
+    }
+
+    if covered.iter().any(|value| !value) {
+        let range_start = u64::from(image.base_address()) + offset as u64;
+
+        return Err(Error::InvalidImage(format!(
+            "{} file doesn't contain data for range 0x{:x}-0x{:x}",
+            image.format_name(),
+            range_start,
+            range_start + size as u64
+        )));
+    }
+
+    Ok(result)
+}
+
+/// Load an image, detecting its format from its content.
+pub fn load(data: Vec<u8>) -> Result<Box<dyn Image>> {
+    if Elf::is_elf(&data) {
+        return Ok(Box::new(Elf::parse(data)?));
+    }
+
+    if IntelHex::is_intel_hex(&data) {
+        return Ok(Box::new(IntelHex::parse(&data)?));
+    }
+
+    if SRecord::is_srecord(&data) {
+        return Ok(Box::new(SRecord::parse(&data)?));
+    }
+
+    Ok(Box::new(BinaryImage { data }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Format an Intel HEX record.
+    fn record(address: u16, record_type: u8, data: &[u8]) -> String {
+        let mut bytes = vec![data.len() as u8];
+        bytes.extend_from_slice(&address.to_be_bytes());
+        bytes.push(record_type);
+        bytes.extend_from_slice(data);
+
+        let checksum = bytes
+            .iter()
+            .fold(0u8, |sum, value| sum.wrapping_sub(*value));
+        bytes.push(checksum);
+
+        let hex: String = bytes.iter().map(|value| format!("{:02X}", value)).collect();
+
+        format!(":{}\n", hex)
+    }
+
+    /// Intel HEX image with initialized data in RAM below the flash bank.
+    fn low_ram_image() -> Vec<u8> {
+        [
+            record(0, 4, &[0x10, 0x00]),
+            record(0, 0, &[0xAA; 8]),
+            record(0, 4, &[0x1A, 0x00]),
+            record(0, 0, &[1, 2, 3, 4, 5, 6, 7, 8]),
+            record(8, 0, &[9, 10, 11, 12]),
+            record(0, 1, &[]),
+        ]
+        .concat()
+        .into_bytes()
+    }
+
+    #[test]
+    fn image_starts_at_flash_base() {
+        let mut image = load(low_ram_image()).unwrap();
+        assert_eq!(image.base_address(), 0x1000_0000);
+
+        image.set_flash_base(&[0x1A00_0000]).unwrap();
+        assert_eq!(image.base_address(), 0x1A00_0000);
+        assert_eq!(image.size(), 12);
+        assert_eq!(image.read_vec(0, 4).unwrap(), [1, 2, 3, 4]);
+
+        image.write(8, &[0xFF; 4]).unwrap();
+        assert_eq!(image.read_vec(8, 4).unwrap(), [0xFF; 4]);
+    }
+
+    #[test]
+    fn gaps_read_as_erased_flash() {
+        let data = [
+            record(0, 4, &[0x1A, 0x00]),
+            record(0, 0, &[1, 2, 3, 4]),
+            record(8, 0, &[5, 6, 7, 8]),
+            record(0, 4, &[0x1B, 0x00]),
+            record(0, 0, &[0xAA; 4]),
+            record(0, 1, &[]),
+        ]
+        .concat()
+        .into_bytes();
+        let image = load(data).unwrap();
+
+        // The second flash bank isn't part of the image.
+        assert_eq!(image.size(), 12);
+        assert_eq!(
+            image.read_all().unwrap(),
+            [1, 2, 3, 4, 0xFF, 0xFF, 0xFF, 0xFF, 5, 6, 7, 8]
+        );
+        assert!(matches!(image.read_vec(0, 12), Err(Error::InvalidImage(_))));
+    }
+
+    #[test]
+    fn flash_base_missing_from_image() {
+        let mut image = load(low_ram_image()).unwrap();
+
+        assert!(matches!(
+            image.set_flash_base(&[0]),
+            Err(Error::InvalidImage(_))
+        ));
+    }
+}
@@ -0,0 +1,389 @@
+//! Motorola S-record file format.
+
+use super::Image;
+use crate::{Error, Result};
+use std::io::Write;
+
+/// A single S-record.
+#[derive(Debug)]
+pub struct Record {
+    /// The type of the record (0 to 9).
+    pub record_type: u8,
+    /// The address field.
+    pub address: u32,
+    /// The payload of the record.
+    pub data: Vec<u8>,
+}
+
+impl Record {
+    /// The size in bytes of the address field of the given record type.
+    fn address_size(record_type: u8) -> Option<usize> {
+        match record_type {
+            0 | 1 | 5 | 9 => Some(2),
+            2 | 6 | 8 => Some(3),
+            3 | 7 => Some(4),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the record contains data bytes (S1, S2 or S3).
+    pub fn is_data(&self) -> bool {
+        (1..=3).contains(&self.record_type)
+    }
+
+    /// Returns true if the record contains the count of data records (S5 or S6).
+    pub fn is_count(&self) -> bool {
+        self.record_type == 5 || self.record_type == 6
+    }
+
+    /// Returns true if the record terminates a block of data records (S7, S8 or S9).
+    pub fn is_termination(&self) -> bool {
+        (7..=9).contains(&self.record_type)
+    }
+
+    /// Get the address, count and data bytes of the record.
+    fn bytes(&self) -> Vec<u8> {
+        let address_size = Self::address_size(self.record_type).unwrap();
+        let mut bytes = vec![(address_size + self.data.len() + 1) as u8];
+
+        bytes.extend_from_slice(&self.address.to_be_bytes()[4 - address_size..]);
+        bytes.extend_from_slice(&self.data);
+
+        bytes
+    }
+
+    /// Compute the checksum byte of the record.
+    pub fn checksum(&self) -> u8 {
+        !self
+            .bytes()
+            .iter()
+            .fold(0u8, |sum, value| sum.wrapping_add(*value))
+    }
+}
+
+/// A Motorola S-record file, keeping the original record layout.
+#[derive(Debug)]
+pub struct SRecord {
+    /// The records of the file.
+    pub records: Vec<Record>,
+    /// The line ending used by the file.
+    line_ending: &'static str,
+    /// Whether the file uses lowercase hexadecimal digits.
+    lowercase: bool,
+    /// The address of the first byte of the image.
+    base_address: u32,
+}
+
+fn invalid_data(line: usize, message: &str) -> Error {
+    Error::InvalidImage(format!("Invalid S-record at line {}: {}", line, message))
+}
+
+impl SRecord {
+    /// Returns true if the given file content looks like a S-record file.
+    pub fn is_srecord(data: &[u8]) -> bool {
+        let mut content = data.iter().skip_while(|value| value.is_ascii_whitespace());
+
+        content.next() == Some(&b'S') && content.next().is_some_and(|value| value.is_ascii_digit())
+    }
+
+    /// Parse a S-record file.
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let text = std::str::from_utf8(data)
+            .map_err(|_| Error::InvalidImage("S-record file isn't valid ASCII".to_string()))?;
+
+        let line_ending = if text.contains("\r\n") { "\r\n" } else { "\n" };
+        let lowercase = text
+            .lines()
+            .any(|line| line.chars().skip(1).any(|value| value.is_ascii_lowercase()));
+        let mut records = Vec::new();
+
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+
+            if !line.is_empty() {
+                records.push(Self::parse_record(i + 1, line)?);
+            }
+        }
+
+        let mut image = SRecord {
+            records,
+            line_ending,
+            lowercase,
+            base_address: 0,
+        };
+        image.base_address = super::lowest_address(&image.chunks());
+
+        Ok(image)
+    }
+
+    fn parse_record(line_number: usize, line: &str) -> Result<Record> {
+        if !line.is_ascii()
+            || !line.starts_with('S')
+            || line.len() < 4
+            || !line.len().is_multiple_of(2)
+        {
+            return Err(invalid_data(line_number, "malformed record"));
+        }
+
+        let record_type = char::from(line.as_bytes()[1])
+            .to_digit(10)
+            .map(|value| value as u8)
+            .filter(|value| *value != 4)
+            .ok_or_else(|| invalid_data(line_number, "unknown record type"))?;
+
+        let bytes = (2..line.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&line[i..i + 2], 16))
+            .collect::<core::result::Result<Vec<u8>, _>>()
+            .map_err(|_| invalid_data(line_number, "invalid hexadecimal digit"))?;
+
+        let address_size = Record::address_size(record_type).unwrap();
+
+        if bytes[0] as usize + 1 != bytes.len() || bytes.len() < address_size + 2 {
+            return Err(invalid_data(line_number, "length mismatch"));
+        }
+
+        let address = bytes[1..=address_size]
+            .iter()
+            .fold(0u32, |address, value| (address << 8) | u32::from(*value));
+
+        let record = Record {
+            record_type,
+            address,
+            data: bytes[address_size + 1..bytes.len() - 1].to_vec(),
+        };
+
+        if record.checksum() != bytes[bytes.len() - 1] {
+            return Err(invalid_data(line_number, "checksum mismatch"));
+        }
+
+        Ok(record)
+    }
+
+    /// Get the address and size of every data record, with the index of the record.
+    fn chunks(&self) -> Vec<(usize, u32, usize)> {
+        self.records
+            .iter()
+            .enumerate()
+            .filter(|(_, record)| record.is_data())
+            .map(|(i, record)| (i, record.address, record.data.len()))
+            .collect()
+    }
+
+    /// Format the bytes and the checksum of a record.
+    fn format_bytes(record: &Record) -> String {
+        let mut result = String::new();
+
+        for value in record.bytes().iter().chain(&[record.checksum()]) {
+            result.push_str(&format!("{:02X}", value));
+        }
+
+        result
+    }
+}
+
+impl Image for SRecord {
+    fn format_name(&self) -> &'static str {
+        "S-record"
+    }
+
+    fn base_address(&self) -> u32 {
+        self.base_address
+    }
+
+    fn size(&self) -> usize {
+        super::end_offset(&self.chunks(), self.base_address)
+    }
+
+    fn read(&self, offset: usize, buffer: &mut [u8]) -> Result<()> {
+        super::covering_overlaps(self, &self.chunks(), offset, buffer.len())?;
+
+        self.read_filled(offset, buffer)
+    }
+
+    fn read_filled(&self, offset: usize, buffer: &mut [u8]) -> Result<()> {
+        let chunks = self.chunks();
+        buffer.fill(super::ERASED_BYTE);
+
+        for (i, record_start, range) in super::overlaps(self, &chunks, offset, buffer.len()) {
+            let record_end = record_start + range.len();
+            buffer[range].copy_from_slice(&self.records[i].data[record_start..record_end]);
+        }
+
+        Ok(())
+    }
+
+    fn write(&mut self, offset: usize, data: &[u8]) -> Result<()> {
+        let chunks = self.chunks();
+
+        for (i, record_start, range) in super::covering_overlaps(self, &chunks, offset, data.len())?
+        {
+            let record_end = record_start + range.len();
+            self.records[i].data[record_start..record_end].copy_from_slice(&data[range]);
+        }
+
+        Ok(())
+    }
+
+    fn set_flash_base(&mut self, addresses: &[u32]) -> Result<()> {
+        self.base_address = super::locate(self.format_name(), &self.chunks(), addresses)?;
+
+        Ok(())
+    }
+
+    fn save(&self, output: &mut dyn Write) -> std::io::Result<()> {
+        let mut data_records_count = 0u32;
+
+        for record in &self.records {
+            let mut line = if record.is_count() {
+                // Regenerate the count of data records preceding this record.
+                let record_type = if data_records_count > 0xFFFF { 6 } else { 5 };
+
+                format!(
+                    "S{}{}",
+                    record_type,
+                    Self::format_bytes(&Record {
+                        record_type,
+                        address: data_records_count,
+                        data: Vec::new(),
+                    })
+                )
+            } else {
+                format!("S{}{}", record.record_type, Self::format_bytes(record))
+            };
+
+            if record.is_data() {
+                data_records_count += 1;
+            } else if record.is_termination() {
+                data_records_count = 0;
+            }
+
+            if self.lowercase {
+                line[1..].make_ascii_lowercase();
+            }
+
+            write!(output, "{}{}", line, self.line_ending)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Image of 12 bytes at 0x1000 in S1, S2 and S3 records, with a stale S5 count.
+    const IMAGE: &str = "S00600004844521B\n\
+                         S107100001020304DE\n\
+                         S20800100405060708C9\n\
+                         S30900001008090A0B0CB4\n\
+                         S5030001FB\n\
+                         S9031000EC\n";
+
+    fn save(image: &SRecord) -> String {
+        let mut output = Vec::new();
+        image.save(&mut output).unwrap();
+
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn data_records() {
+        let image = SRecord::parse(IMAGE.as_bytes()).unwrap();
+
+        assert_eq!(image.base_address(), 0x1000);
+        assert_eq!(image.size(), 12);
+        assert_eq!(
+            image.read_vec(0, 12).unwrap(),
+            [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12]
+        );
+        assert_eq!(
+            image
+                .records
+                .iter()
+                .map(|record| (record.record_type, record.address))
+                .collect::<Vec<_>>(),
+            [
+                (0, 0),
+                (1, 0x1000),
+                (2, 0x1004),
+                (3, 0x1008),
+                (5, 1),
+                (9, 0x1000)
+            ]
+        );
+    }
+
+    #[test]
+    fn save_regenerates_count() {
+        let mut image = SRecord::parse(IMAGE.as_bytes()).unwrap();
+        assert_eq!(save(&image), IMAGE.replace("S5030001FB", "S5030003F9"));
+
+        image.write(0, &[0xAA]).unwrap();
+        assert!(save(&image).contains("\nS1071000AA02030435\n"));
+
+        // The record type prefix stays uppercase.
+        let text = IMAGE
+            .lines()
+            .map(|line| format!("S{}\r\n", line[1..].to_ascii_lowercase()))
+            .collect::<String>();
+        let image = SRecord::parse(text.as_bytes()).unwrap();
+        assert_eq!(save(&image), text.replace("S5030001fb", "S5030003f9"));
+    }
+
+    #[test]
+    fn save_switches_to_s6() {
+        let mut text = (0..0x10000u32)
+            .map(|address| {
+                let record = Record {
+                    record_type: 1,
+                    address,
+                    data: vec![address as u8],
+                };
+
+                format!("S1{}\n", SRecord::format_bytes(&record))
+            })
+            .collect::<String>();
+        text.push_str("S5030001FB\nS9030000FC\n");
+
+        let image = SRecord::parse(text.as_bytes()).unwrap();
+        assert!(save(&image).ends_with("\nS604010000FA\nS9030000FC\n"));
+    }
+
+    #[test]
+    fn termination_records() {
+        for (trailer, record_type, address) in [
+            ("S9031000EC", 9, 0x1000),
+            ("S804001000EB", 8, 0x1000),
+            ("S7051A000000E0", 7, 0x1A00_0000),
+        ] {
+            let text = format!("S3071A0000000102DB\n{}\n", trailer);
+            let image = SRecord::parse(text.as_bytes()).unwrap();
+            let record = image.records.last().unwrap();
+
+            assert!(record.is_termination());
+            assert_eq!((record.record_type, record.address), (record_type, address));
+            assert_eq!(image.base_address(), 0x1A00_0000);
+            assert_eq!(save(&image), text);
+        }
+    }
+
+    #[test]
+    fn invalid_records() {
+        for (text, message) in [
+            ("S107100001020304DF\n", "checksum mismatch"),
+            ("S108100001020304DE\n", "length mismatch"),
+            ("S1071000010203G4DE\n", "invalid hexadecimal digit"),
+            ("S4030001FB\n", "unknown record type"),
+            ("S10710000102030\n", "malformed record"),
+        ] {
+            match SRecord::parse(text.as_bytes()) {
+                Err(Error::InvalidImage(error)) => {
+                    assert_eq!(error, format!("Invalid S-record at line 1: {}", message))
+                }
+                result => panic!("{:?} parsed as {:?}", text, result),
+            }
+        }
+    }
+}
@@ -0,0 +1,441 @@
+//! Handle LPC BootROM checksum calculation for various LPC processor.
+//!
+//! The LPC BootROM only considers a user image valid if a specific word of the vector table
+//! contains the two's complement of the sum of the other vector table entries.
+//! This crate computes, inserts and verifies that value.
+//!
+//! Without the default `std` feature, only the allocation free slice based API is available.
+//! The default `secure` feature adds the signed images, protected flash region pages and secure
+//! binary files of the LPC5500 familly, and the encrypted boot images of the LPC18Sxx and
+//! LPC43Sxx.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+pub mod boot_header;
+pub mod checksum;
+pub mod crp;
+mod error;
+#[cfg(feature = "std")]
+pub mod image;
+pub mod lpc54;
+pub mod lpc55;
+pub mod part;
+#[cfg(feature = "secure")]
+pub mod pfr;
+#[cfg(feature = "secure")]
+pub mod prince;
+#[cfg(feature = "std")]
+pub mod report;
+#[cfg(feature = "secure")]
+pub mod sb2;
+pub mod signature;
+#[cfg(feature = "secure")]
+pub mod signed;
+pub mod vectors;
+
+pub use error::{Error, Result};
+
+use part::{Family, Part};
+
+use core::convert::TryInto;
+#[cfg(feature = "std")]
+use std::io::{Read, Seek, SeekFrom, Write};
+
+/// Size of a word in bytes.
+const WORD_SIZE: usize = core::mem::size_of::<u32>();
+
+/// Structure used to define information needed to compute checksum on the various LPC processor.
+#[derive(Debug)]
+pub struct ProcessorChecksumInfo {
+    /// The name of the CPU familly.
+    pub cpu_family: &'static str,
+    /// The count of words used for checksum
+    pub words_count: Option<usize>,
+    /// The word position of the checksum value.
+    pub resulting_word_position: usize,
+}
+
+impl ProcessorChecksumInfo {
+    /// Returns true if the BootROM of this familly validates a checksum.
+    pub fn is_supported(&self) -> bool {
+        self.words_count.is_some()
+    }
+
+    /// The count of words of the image header needed to compute and store the checksum.
+    pub fn header_words_count(&self) -> Option<usize> {
+        self.words_count
+            .map(|words_count| core::cmp::max(words_count, self.resulting_word_position + 1))
+    }
+
+    /// The offset in bytes of the checksum value in the image.
+    pub fn checksum_offset(&self) -> usize {
+        self.resulting_word_position * WORD_SIZE
+    }
+
+    /// Compute the checksum of the given vector table words.
+    ///
+    /// Returns `None` if the checksum isn't supported or if there isn't enough words.
+    pub fn compute_checksum_words(&self, words: &[u32]) -> Option<u32> {
+        if words.len() < self.header_words_count()? {
+            return None;
+        }
+
+        Some(checksum::compute_checksum(
+            words[..self.words_count?].iter().copied(),
+            self.resulting_word_position,
+        ))
+    }
+
+    /// Check that the word at the checksum position matches the computed checksum.
+    pub fn verify_checksum_words(&self, words: &[u32]) -> bool {
+        self.compute_checksum_words(words) == words.get(self.resulting_word_position).copied()
+    }
+
+    /// Compute the checksum of the vector table located at the given address.
+    ///
+    /// Returns `None` if the checksum isn't supported.
+    ///
+    /// # Safety
+    ///
+    /// `address` must be aligned and valid for reads of the words used by the checksum.
+    pub unsafe fn compute_checksum_raw(&self, address: *const u32) -> Option<u32> {
+        Some(checksum::compute_checksum_raw(
+            address,
+            self.words_count?,
+            self.resulting_word_position,
+        ))
+    }
+
+    /// Check that the vector table located at the given address has a valid checksum.
+    ///
+    /// This is the check done by the BootROM before starting the user image.
+    ///
+    /// # Safety
+    ///
+    /// `address` must be aligned and valid for reads of the whole image header.
+    pub unsafe fn verify_checksum_raw(&self, address: *const u32) -> bool {
+        self.compute_checksum_raw(address)
+            == Some(core::ptr::read_volatile(
+                address.add(self.resulting_word_position),
+            ))
+    }
+
+    /// The size in bytes of the image header needed to compute and store the checksum.
+    pub fn header_size(&self) -> Result<usize> {
+        let words_count = self
+            .header_words_count()
+            .ok_or(Error::UnsupportedFamily(self.cpu_family))?;
+
+        Ok(words_count * WORD_SIZE)
+    }
+
+    /// Compute the checksum of the given image.
+    pub fn compute_checksum(&self, image: &[u8]) -> Result<u32> {
+        let header_size = self.header_size()?;
+
+        if image.len() < header_size {
+            return Err(Error::ImageTooShort {
+                required: header_size,
+                actual: image.len(),
+            });
+        }
+
+        Ok(checksum::compute_checksum(
+            image[..self.words_count.unwrap() * WORD_SIZE]
+                .chunks(WORD_SIZE)
+                .map(|value| u32::from_le_bytes(value.try_into().unwrap())),
+            self.resulting_word_position,
+        ))
+    }
+
+    /// Compute the checksum of the given image and write it at the checksum position.
+    ///
+    /// Returns the inserted checksum.
+    pub fn insert_checksum(&self, image: &mut [u8]) -> Result<u32> {
+        let checksum = self.compute_checksum(image)?;
+        let offset = self.checksum_offset();
+
+        image[offset..offset + WORD_SIZE].copy_from_slice(&checksum.to_le_bytes());
+
+        Ok(checksum)
+    }
+
+    /// Read the value currently stored at the checksum position of the given image.
+    pub fn read_checksum(&self, image: &[u8]) -> Result<u32> {
+        self.compute_checksum(image)?;
+        let offset = self.checksum_offset();
+
+        Ok(u32::from_le_bytes(
+            image[offset..offset + WORD_SIZE].try_into().unwrap(),
+        ))
+    }
+
+    /// Check that the value at the checksum position matches the computed checksum.
+    ///
+    /// Returns the checksum, or [`Error::ChecksumMismatch`] if the stored value is wrong.
+    pub fn verify_checksum(&self, image: &[u8]) -> Result<u32> {
+        let expected = self.compute_checksum(image)?;
+        let found = self.read_checksum(image)?;
+
+        if expected != found {
+            return Err(Error::ChecksumMismatch { expected, found });
+        }
+
+        Ok(found)
+    }
+
+    /// Read the image header from the start of the given stream.
+    #[cfg(feature = "std")]
+    fn read_header<T: Read + Seek>(&self, firmware: &mut T) -> Result<Vec<u8>> {
+        let mut header = vec![0; self.header_size()?];
+        let actual = firmware.seek(SeekFrom::End(0))? as usize;
+
+        if actual < header.len() {
+            return Err(Error::ImageTooShort {
+                required: header.len(),
+                actual,
+            });
+        }
+
+        firmware.seek(SeekFrom::Start(0))?;
+        firmware.read_exact(&mut header)?;
+
+        Ok(header)
+    }
+
+    /// Compute the checksum of the image contained in the given stream.
+    #[cfg(feature = "std")]
+    pub fn compute_checksum_from<T: Read + Seek>(&self, firmware: &mut T) -> Result<u32> {
+        let header = self.read_header(firmware)?;
+
+        self.compute_checksum(&header)
+    }
+
+    /// Compute the checksum of the image contained in the given stream and write it at the checksum position.
+    ///
+    /// Returns the inserted checksum.
+    #[cfg(feature = "std")]
+    pub fn insert_checksum_into<T: Read + Seek + Write>(&self, firmware: &mut T) -> Result<u32> {
+        let checksum = self.compute_checksum_from(firmware)?;
+
+        firmware.seek(SeekFrom::Start(self.checksum_offset() as u64))?;
+        firmware.write_all(&checksum.to_le_bytes())?;
+
+        Ok(checksum)
+    }
+
+    /// Read the value currently stored at the checksum position of the given stream.
+    #[cfg(feature = "std")]
+    pub fn read_checksum_from<T: Read + Seek>(&self, firmware: &mut T) -> Result<u32> {
+        let header = self.read_header(firmware)?;
+
+        self.read_checksum(&header)
+    }
+
+    /// Check that the value at the checksum position of the given stream matches the computed checksum.
+    ///
+    /// Returns the checksum, or [`Error::ChecksumMismatch`] if the stored value is wrong.
+    #[cfg(feature = "std")]
+    pub fn verify_checksum_from<T: Read + Seek>(&self, firmware: &mut T) -> Result<u32> {
+        let header = self.read_header(firmware)?;
+
+        self.verify_checksum(&header)
+    }
+}
+
+/// Checksum information of all the supported LPC processor famillies.
+pub static PROCESSOR_CHECKSUM: &[ProcessorChecksumInfo] = &[
+    // LPC3 doesn't suppoort checksum validation.
+    ProcessorChecksumInfo {
+        cpu_family: "LPC3",
+        words_count: None,
+        resulting_word_position: 0,
+    },
+    // LPC29 doesn't suppoort checksum validation.
+    ProcessorChecksumInfo {
+        cpu_family: "LPC29",
+        words_count: None,
+        resulting_word_position: 0,
+    },
+    ProcessorChecksumInfo {
+        cpu_family: "LPC1",
+        words_count: Some(7),
+        resulting_word_position: 7,
+    },
+    ProcessorChecksumInfo {
+        cpu_family: "LPC2",
+        words_count: Some(8),
+        resulting_word_position: 5,
+    },
+    ProcessorChecksumInfo {
+        cpu_family: "LPC4",
+        words_count: Some(7),
+        resulting_word_position: 7,
+    },
+    ProcessorChecksumInfo {
+        cpu_family: "LPC5",
+        words_count: Some(7),
+        resulting_word_position: 7,
+    },
+    ProcessorChecksumInfo {
+        cpu_family: "LPC8",
+        words_count: Some(7),
+        resulting_word_position: 7,
+    },
+];
+
+/// Get the checksum information of the given processor part number (e.g. LPC1768, or LPC2103) or familly (e.g. LPC1700).
+pub fn get_processor_checksum_info_by_name(
+    cpu_part_number: &str,
+) -> Option<&'static ProcessorChecksumInfo> {
+    part::get_part_by_name(cpu_part_number)
+        .map(Part::checksum_info)
+        .or_else(|| Family::from_name(cpu_part_number).map(Family::checksum_info))
+        .or_else(|| Family::from_part_number(cpu_part_number).map(Family::checksum_info))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build an image from little endian words.
+    fn image(words: &[u32]) -> [u8; 64] {
+        let mut image = [0xFF; 64];
+
+        for (chunk, word) in image.chunks_mut(WORD_SIZE).zip(words) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+
+        image
+    }
+
+    fn family_info(cpu_family: &str) -> &'static ProcessorChecksumInfo {
+        PROCESSOR_CHECKSUM
+            .iter()
+            .find(|info| info.cpu_family == cpu_family)
+            .unwrap()
+    }
+
+    #[test]
+    fn cortex_m_layout() {
+        let info = get_processor_checksum_info_by_name("LPC1768").unwrap();
+        let mut image = image(&[1, 2, 3, 4, 5, 6, 7]);
+
+        // The first 7 words are summed, the checksum being the 8th one.
+        assert_eq!(info.cpu_family, "LPC1");
+        assert_eq!(info.header_size().unwrap(), 32);
+        assert_eq!(
+            info.insert_checksum(&mut image).unwrap(),
+            0u32.wrapping_sub(28)
+        );
+        assert_eq!(image[28..32], 0u32.wrapping_sub(28).to_le_bytes());
+        assert_eq!(image[32..], [0xFF; 32]);
+    }
+
+    #[test]
+    fn arm7_layout() {
+        let info = get_processor_checksum_info_by_name("LPC2129").unwrap();
+        let mut image = image(&[1, 2, 3, 4, 5, 0xDEAD_BEEF, 7, 8]);
+
+        // The 8 exception vectors are summed, the checksum being the reserved 6th one.
+        assert_eq!(info.cpu_family, "LPC2");
+        assert_eq!(info.header_size().unwrap(), 32);
+        assert_eq!(
+            info.insert_checksum(&mut image).unwrap(),
+            0u32.wrapping_sub(30)
+        );
+        assert_eq!(image[20..24], 0u32.wrapping_sub(30).to_le_bytes());
+        assert_eq!(image[28..32], 8u32.to_le_bytes());
+    }
+
+    #[test]
+    fn vector_table_words() {
+        let info = family_info("LPC4");
+        let mut words = [0x1000_2000, 0x101, 0x103, 0x105, 0x107, 0x109, 0x10B, 0];
+
+        assert_eq!(info.compute_checksum_words(&words[..7]), None);
+        assert!(!info.verify_checksum_words(&words));
+        assert!(!unsafe { info.verify_checksum_raw(words.as_ptr()) });
+
+        words[7] = info.compute_checksum_words(&words).unwrap();
+        assert_eq!(
+            words.iter().fold(0u32, |sum, word| sum.wrapping_add(*word)),
+            0
+        );
+        assert!(info.verify_checksum_words(&words));
+        assert!(unsafe { info.verify_checksum_raw(words.as_ptr()) });
+        assert_eq!(
+            unsafe { info.compute_checksum_raw(words.as_ptr()) },
+            Some(words[7])
+        );
+
+        let lpc3 = family_info("LPC3");
+        assert_eq!(lpc3.compute_checksum_words(&words), None);
+        assert_eq!(unsafe { lpc3.compute_checksum_raw(words.as_ptr()) }, None);
+    }
+
+    #[test]
+    fn insert_then_verify() {
+        let info = family_info("LPC2");
+        let mut image = image(&[0xEA00_0018, 1, 2, 3, 4, 0, 5, 6]);
+
+        assert!(matches!(
+            info.verify_checksum(&image),
+            Err(Error::ChecksumMismatch { found: 0, .. })
+        ));
+
+        let checksum = info.insert_checksum(&mut image).unwrap();
+        assert_eq!(info.verify_checksum(&image).unwrap(), checksum);
+        assert_eq!(info.read_checksum(&image).unwrap(), checksum);
+
+        // Any other vector changes the expected checksum.
+        image[4] ^= 1;
+        assert!(matches!(
+            info.verify_checksum(&image),
+            Err(Error::ChecksumMismatch { expected, found })
+                if found == checksum && expected == checksum.wrapping_add(1)
+        ));
+    }
+
+    #[test]
+    fn image_too_short() {
+        let info = family_info("LPC1");
+
+        for image in [&[0; 31][..], &[]] {
+            assert!(matches!(
+                info.compute_checksum(image),
+                Err(Error::ImageTooShort {
+                    required: 32,
+                    actual
+                }) if actual == image.len()
+            ));
+        }
+
+        assert!(matches!(
+            family_info("LPC3").compute_checksum(&[0; 64]),
+            Err(Error::UnsupportedFamily("LPC3"))
+        ));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn stream() {
+        let info = family_info("LPC8");
+        let mut firmware = std::io::Cursor::new(image(&[1, 2, 3, 4, 5, 6, 7]).to_vec());
+
+        let checksum = info.insert_checksum_into(&mut firmware).unwrap();
+        assert_eq!(checksum, 0u32.wrapping_sub(28));
+        assert_eq!(info.read_checksum_from(&mut firmware).unwrap(), checksum);
+        assert_eq!(info.compute_checksum_from(&mut firmware).unwrap(), checksum);
+        assert_eq!(firmware.get_ref().len(), 64);
+        assert_eq!(info.verify_checksum_from(&mut firmware).unwrap(), checksum);
+
+        assert!(matches!(
+            info.compute_checksum_from(&mut std::io::Cursor::new([0; 16])),
+            Err(Error::ImageTooShort {
+                required: 32,
+                actual: 16
+            })
+        ));
+    }
+}
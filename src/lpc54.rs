@@ -0,0 +1,378 @@
+//! Enhanced boot block of the LPC5400 familly.
+//!
+//! The vector table of an enhanced image holds a marker at 0x24 followed by the address of the
+//! boot block, describing how the BootROM loads the image (e.g. from SPIFI on flashless parts).
+
+use crate::checksum;
+#[cfg(feature = "std")]
+use crate::image::{BinaryImage, Image};
+#[cfg(feature = "std")]
+use crate::part::Part;
+use crate::{Error, Result};
+use core::convert::TryInto;
+
+/// Offset of the enhanced image marker in the vector table.
+pub const MARKER_OFFSET: usize = 0x24;
+
+/// Offset of the boot block address in the vector table.
+pub const POINTER_OFFSET: usize = 0x28;
+
+/// Marker of an enhanced image.
+pub const ENHANCED_IMAGE_MARKER: u32 = 0xEDDC_94BD;
+
+/// Marker at the start of the boot block.
+pub const BOOT_BLOCK_MARKER: u32 = 0xFEED_A5A5;
+
+/// Size in bytes of the boot block fields handled.
+pub const BOOT_BLOCK_SIZE: usize = 0x18;
+
+/// Offset of the CRC32 in the boot block.
+const CRC_OFFSET: usize = 0x10;
+
+/// Image type of an image executed in place.
+pub const IMAGE_TYPE_XIP: u32 = 0x0;
+
+/// Image type of an image copied to RAM at its load address.
+pub const IMAGE_TYPE_RAM: u32 = 0x1;
+
+/// Enhanced boot block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(serde::Serialize))]
+pub struct BootBlock {
+    /// The image type.
+    pub image_type: u32,
+    /// The address the image is loaded at.
+    pub load_address: u32,
+    /// The length in bytes of the image.
+    pub image_length: u32,
+    /// The CRC32 of the image.
+    pub crc: u32,
+    /// The version of the image.
+    pub version: u32,
+}
+
+fn read_word(image: &[u8], offset: usize) -> Result<u32> {
+    image
+        .get(offset..offset + 4)
+        .map(|word| u32::from_le_bytes(word.try_into().unwrap()))
+        .ok_or(Error::ImageTooShort {
+            required: offset + 4,
+            actual: image.len(),
+        })
+}
+
+impl BootBlock {
+    /// Parse the boot block at the given offset of the image.
+    pub fn parse(image: &[u8], offset: usize) -> Result<Self> {
+        if read_word(image, offset)? != BOOT_BLOCK_MARKER {
+            return Err(Error::InvalidBootHeader("invalid boot block marker"));
+        }
+
+        Ok(BootBlock {
+            image_type: read_word(image, offset + 0x4)?,
+            load_address: read_word(image, offset + 0x8)?,
+            image_length: read_word(image, offset + 0xC)?,
+            crc: read_word(image, offset + CRC_OFFSET)?,
+            version: read_word(image, offset + 0x14)?,
+        })
+    }
+
+    /// Encode the boot block.
+    pub fn to_bytes(&self) -> [u8; BOOT_BLOCK_SIZE] {
+        let mut boot_block = [0; BOOT_BLOCK_SIZE];
+
+        for (i, value) in [
+            BOOT_BLOCK_MARKER,
+            self.image_type,
+            self.load_address,
+            self.image_length,
+            self.crc,
+            self.version,
+        ]
+        .iter()
+        .enumerate()
+        {
+            boot_block[i * 4..i * 4 + 4].copy_from_slice(&value.to_le_bytes());
+        }
+
+        boot_block
+    }
+
+    /// Returns true if the image is executed in place.
+    pub fn is_xip(&self) -> bool {
+        self.image_type == IMAGE_TYPE_XIP
+    }
+}
+
+/// Get the offset of the boot block of an enhanced image linked at the given address.
+///
+/// Returns `None` if the image isn't an enhanced image.
+pub fn boot_block_offset(image: &[u8], link_address: u32) -> Result<Option<usize>> {
+    if read_word(image, MARKER_OFFSET)? != ENHANCED_IMAGE_MARKER {
+        return Ok(None);
+    }
+
+    let address = read_word(image, POINTER_OFFSET)?;
+
+    address
+        .checked_sub(link_address)
+        .map(|offset| offset as usize)
+        .filter(|offset| offset + BOOT_BLOCK_SIZE <= image.len())
+        .map(Some)
+        .ok_or(Error::InvalidBootHeader(
+            "boot block outside the image, check its link address",
+        ))
+}
+
+/// Compute the CRC32 of the image, skipping the CRC value of the boot block at the given offset.
+pub fn compute_crc(image: &[u8], offset: usize) -> Result<u32> {
+    let crc_offset = offset + CRC_OFFSET;
+
+    read_word(image, crc_offset)?;
+
+    Ok(checksum::crc32(
+        image[..crc_offset].iter().chain(&image[crc_offset + 4..]),
+    ))
+}
+
+/// Append a boot block to a binary image linked at the given address, marking it as enhanced.
+///
+/// Returns the offset of the boot block, its length and CRC being left to be filled.
+#[cfg(feature = "std")]
+pub fn append_boot_block(
+    image: &mut Vec<u8>,
+    link_address: u32,
+    boot_block: &mut BootBlock,
+) -> Result<usize> {
+    read_word(image, POINTER_OFFSET)?;
+
+    image.resize(image.len().next_multiple_of(4), 0);
+
+    let offset = image.len();
+    let address = link_address
+        .checked_add(offset as u32)
+        .ok_or(Error::InvalidBootHeader("image too large"))?;
+
+    image[MARKER_OFFSET..MARKER_OFFSET + 4].copy_from_slice(&ENHANCED_IMAGE_MARKER.to_le_bytes());
+    image[POINTER_OFFSET..POINTER_OFFSET + 4].copy_from_slice(&address.to_le_bytes());
+
+    boot_block.image_length = (offset + BOOT_BLOCK_SIZE) as u32;
+    image.extend_from_slice(&boot_block.to_bytes());
+
+    Ok(offset)
+}
+
+/// Make a binary image linked at the given address an enhanced image of the given image type,
+/// appending its boot block.
+#[cfg(feature = "std")]
+pub fn enhance_image(
+    firmware: &dyn Image,
+    link_address: u32,
+    image_type: u32,
+) -> Result<BinaryImage> {
+    if firmware.format_name() != "binary" {
+        return Err(Error::InvalidImage(
+            "Enhanced boot block only supported for binary images".to_string(),
+        ));
+    }
+
+    let mut data = firmware.read_all()?;
+
+    append_boot_block(
+        &mut data,
+        link_address,
+        &mut BootBlock {
+            image_type,
+            load_address: link_address,
+            image_length: 0,
+            crc: 0,
+            version: 0,
+        },
+    )?;
+
+    Ok(BinaryImage { data })
+}
+
+/// Find the boot block of an image linked at the given address, the gaps between the data being
+/// erased flash.
+///
+/// Returns `None` if the image isn't an enhanced image, `handler` being called with a warning if
+/// the part is flashless.
+#[cfg(feature = "std")]
+pub fn find_boot_block<F: FnMut(String)>(
+    firmware: &dyn Image,
+    part: Option<&Part>,
+    link_address: u32,
+    mut handler: F,
+) -> Result<Option<(usize, BootBlock)>> {
+    let data = firmware.read_all()?;
+
+    match boot_block_offset(&data, link_address)? {
+        Some(offset) => Ok(Some((offset, BootBlock::parse(&data, offset)?))),
+        None => {
+            if part.is_some_and(|part| part.flash.is_empty()) {
+                handler("Image of a flashless part without enhanced boot block".to_string());
+            }
+
+            Ok(None)
+        }
+    }
+}
+
+/// Validate the boot block of an image linked at the given address against the image size and
+/// the memory map of the part.
+///
+/// `handler` is called for every warning.
+#[cfg(feature = "std")]
+pub fn check_boot_block<F: FnMut(String)>(
+    boot_block: &BootBlock,
+    firmware: &dyn Image,
+    part: Option<&Part>,
+    link_address: u32,
+    mut handler: F,
+) -> Result<()> {
+    if boot_block.image_length as usize != firmware.size() {
+        return Err(Error::InvalidBootHeader(
+            "image length doesn't match the image size",
+        ));
+    }
+
+    if boot_block.load_address != link_address {
+        handler(format!(
+            "Load address 0x{:08x} isn't the image link address 0x{:08x}",
+            boot_block.load_address, link_address
+        ));
+    }
+
+    let in_ram = |part: &Part| {
+        part.ram
+            .iter()
+            .any(|region| region.contains(boot_block.load_address))
+    };
+
+    if !boot_block.is_xip() && part.is_some_and(|part| !in_ram(part)) {
+        handler(format!(
+            "Load address 0x{:08x} of a RAM image outside RAM",
+            boot_block.load_address
+        ));
+    }
+
+    Ok(())
+}
+
+/// Compute the CRC of an enhanced image with the given patched vector table and boot block
+/// offset, the gaps between the data being erased flash, writing it in the boot block unless
+/// verifying.
+///
+/// Returns the CRC, and the mismatch to report once the checksum is verified.
+#[cfg(feature = "std")]
+pub fn update_crc(
+    firmware: &dyn Image,
+    vectors: &[u8],
+    offset: usize,
+    boot_block: &mut BootBlock,
+    verify: bool,
+) -> Result<(u32, Option<Error>)> {
+    let mut data = firmware.read_all()?;
+    data[..vectors.len()].copy_from_slice(vectors);
+
+    let crc = compute_crc(&data, offset)?;
+
+    if !verify {
+        boot_block.crc = crc;
+    } else if boot_block.crc != crc {
+        return Ok((
+            crc,
+            Some(Error::CrcMismatch {
+                expected: crc,
+                found: boot_block.crc,
+            }),
+        ));
+    }
+
+    Ok((crc, None))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LINK_ADDRESS: u32 = 0x1000_0000;
+
+    fn image() -> Vec<u8> {
+        (0..0x101).map(|i| i as u8).collect()
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn append_and_locate() {
+        let mut image = image();
+        let mut boot_block = BootBlock {
+            image_type: IMAGE_TYPE_RAM,
+            load_address: LINK_ADDRESS,
+            image_length: 0,
+            crc: 0,
+            version: 1,
+        };
+
+        // The boot block is word aligned at the end of the image.
+        let offset = append_boot_block(&mut image, LINK_ADDRESS, &mut boot_block).unwrap();
+        assert_eq!(offset, 0x104);
+        assert_eq!(image.len(), 0x104 + BOOT_BLOCK_SIZE);
+        assert_eq!(boot_block.image_length as usize, image.len());
+
+        assert_eq!(
+            boot_block_offset(&image, LINK_ADDRESS).unwrap(),
+            Some(offset)
+        );
+        assert_eq!(BootBlock::parse(&image, offset).unwrap(), boot_block);
+        assert!(!boot_block.is_xip());
+
+        // The CRC skips its own value.
+        let crc = compute_crc(&image, offset).unwrap();
+        image[offset + CRC_OFFSET..offset + CRC_OFFSET + 4].copy_from_slice(&crc.to_le_bytes());
+        assert_eq!(compute_crc(&image, offset).unwrap(), crc);
+    }
+
+    #[test]
+    fn plain_image() {
+        assert_eq!(boot_block_offset(&image(), LINK_ADDRESS).unwrap(), None);
+        assert!(matches!(
+            boot_block_offset(&image()[..MARKER_OFFSET], LINK_ADDRESS),
+            Err(Error::ImageTooShort { .. })
+        ));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn pointer_outside_image() {
+        let mut image = image();
+        let offset = append_boot_block(
+            &mut image,
+            LINK_ADDRESS,
+            &mut BootBlock {
+                image_type: IMAGE_TYPE_XIP,
+                load_address: LINK_ADDRESS,
+                image_length: 0,
+                crc: 0,
+                version: 0,
+            },
+        )
+        .unwrap();
+        let address = LINK_ADDRESS + offset as u32;
+
+        // Linked at another address, the boot block is past the end or below the image.
+        for link_address in [LINK_ADDRESS - 0x100, address + 4] {
+            assert!(matches!(
+                boot_block_offset(&image, link_address),
+                Err(Error::InvalidBootHeader(_))
+            ));
+        }
+
+        image[offset] ^= 1;
+        assert!(matches!(
+            BootBlock::parse(&image, offset),
+            Err(Error::InvalidBootHeader(_))
+        ));
+    }
+}
@@ -0,0 +1,378 @@
+//! Image header of the LPC5500 familly.
+//!
+//! The LPC5500 BootROM reads the image length, type and load address from reserved entries of
+//! the vector table. CRC images also store the CRC32 of the whole image in the header.
+
+use crate::checksum;
+#[cfg(feature = "std")]
+use crate::image::Image;
+#[cfg(feature = "std")]
+use crate::part::Part;
+use crate::{Error, Result};
+use core::convert::TryInto;
+
+/// Offset of the image length.
+pub const IMAGE_LENGTH_OFFSET: usize = 0x20;
+
+/// Offset of the image type.
+pub const IMAGE_TYPE_OFFSET: usize = 0x24;
+
+/// Offset of the CRC32 of CRC images, or of the certificate block offset of signed images.
+pub const SPECIFIC_HEADER_OFFSET: usize = 0x28;
+
+/// Offset of the load address.
+pub const LOAD_ADDRESS_OFFSET: usize = 0x34;
+
+/// Size in bytes of the vector table part holding the header.
+pub const HEADER_SIZE: usize = 0x38;
+
+/// Type of a LPC5500 image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageType {
+    /// Plain image executed in place.
+    Plain,
+    /// Signed image copied to RAM.
+    Signed,
+    /// Image with CRC copied to RAM.
+    CrcRam,
+    /// Signed image executed in place.
+    SignedXip,
+    /// Image with CRC executed in place.
+    CrcXip,
+}
+
+impl ImageType {
+    /// All the image types.
+    pub const ALL: &'static [ImageType] = &[
+        ImageType::Plain,
+        ImageType::Signed,
+        ImageType::CrcRam,
+        ImageType::SignedXip,
+        ImageType::CrcXip,
+    ];
+
+    /// The value of the image type field, without flags.
+    pub fn value(self) -> u32 {
+        match self {
+            ImageType::Plain => 0x0,
+            ImageType::Signed => 0x1,
+            ImageType::CrcRam => 0x2,
+            ImageType::SignedXip => 0x4,
+            ImageType::CrcXip => 0x5,
+        }
+    }
+
+    /// Decode the image type field, ignoring the flags.
+    pub fn from_value(value: u32) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|image_type| image_type.value() == value & 0xFF)
+    }
+
+    /// The name of the image type.
+    pub fn name(self) -> &'static str {
+        match self {
+            ImageType::Plain => "plain",
+            ImageType::Signed => "signed",
+            ImageType::CrcRam => "crc-ram",
+            ImageType::SignedXip => "signed-xip",
+            ImageType::CrcXip => "crc-xip",
+        }
+    }
+
+    /// Get an image type by its name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|image_type| image_type.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Returns true if the image stores a CRC32 in its header.
+    pub fn is_crc(self) -> bool {
+        self == ImageType::CrcRam || self == ImageType::CrcXip
+    }
+
+    /// Returns true if the image is signed.
+    pub fn is_signed(self) -> bool {
+        self == ImageType::Signed || self == ImageType::SignedXip
+    }
+
+    /// Returns true if the image is executed in place from flash.
+    pub fn is_xip(self) -> bool {
+        !matches!(self, ImageType::Signed | ImageType::CrcRam)
+    }
+}
+
+/// Image header stored in the vector table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(serde::Serialize))]
+pub struct ImageHeader {
+    /// The length in bytes of the image.
+    pub image_length: u32,
+    /// The image type field, with its flags.
+    pub image_type: u32,
+    /// The CRC32 of CRC images, or the certificate block offset of signed images.
+    pub specific_header: u32,
+    /// The address the image is loaded at.
+    pub load_address: u32,
+}
+
+fn read_word(image: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(image[offset..offset + 4].try_into().unwrap())
+}
+
+fn check_size(image: &[u8]) -> Result<()> {
+    if image.len() < HEADER_SIZE {
+        return Err(Error::ImageTooShort {
+            required: HEADER_SIZE,
+            actual: image.len(),
+        });
+    }
+
+    Ok(())
+}
+
+impl ImageHeader {
+    /// Parse the header of the given image.
+    pub fn parse(image: &[u8]) -> Result<Self> {
+        check_size(image)?;
+
+        Ok(ImageHeader {
+            image_length: read_word(image, IMAGE_LENGTH_OFFSET),
+            image_type: read_word(image, IMAGE_TYPE_OFFSET),
+            specific_header: read_word(image, SPECIFIC_HEADER_OFFSET),
+            load_address: read_word(image, LOAD_ADDRESS_OFFSET),
+        })
+    }
+
+    /// Write the header in the given image.
+    pub fn write(&self, image: &mut [u8]) -> Result<()> {
+        check_size(image)?;
+
+        for (offset, value) in [
+            (IMAGE_LENGTH_OFFSET, self.image_length),
+            (IMAGE_TYPE_OFFSET, self.image_type),
+            (SPECIFIC_HEADER_OFFSET, self.specific_header),
+            (LOAD_ADDRESS_OFFSET, self.load_address),
+        ] {
+            image[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+        }
+
+        Ok(())
+    }
+
+    /// Get the decoded image type, `None` if unknown.
+    pub fn image_type(&self) -> Option<ImageType> {
+        ImageType::from_value(self.image_type)
+    }
+}
+
+/// Compute the CRC32 of a CRC image, skipping the CRC value itself.
+pub fn compute_crc(image: &[u8]) -> Result<u32> {
+    check_size(image)?;
+
+    Ok(checksum::crc32(
+        image[..SPECIFIC_HEADER_OFFSET]
+            .iter()
+            .chain(&image[SPECIFIC_HEADER_OFFSET + 4..]),
+    ))
+}
+
+/// Write the header of the image if an image type is given, its length being the image size and
+/// its load address the image base address by default, then parse it.
+#[cfg(feature = "std")]
+pub fn process_image_header(
+    firmware: &mut dyn Image,
+    image_type: Option<ImageType>,
+    load_address: Option<u32>,
+) -> Result<ImageHeader> {
+    let mut data = firmware.read_vec(0, HEADER_SIZE)?;
+    let mut header = ImageHeader::parse(&data)?;
+
+    if let Some(image_type) = image_type {
+        header.image_length = firmware.size() as u32;
+        header.image_type = image_type.value();
+        header.load_address = load_address.unwrap_or_else(|| firmware.base_address());
+
+        header.write(&mut data)?;
+        firmware.write(0, &data)?;
+    }
+
+    header
+        .image_type()
+        .ok_or(Error::InvalidBootHeader("unknown LPC5500 image type"))?;
+
+    Ok(header)
+}
+
+/// Validate the header of the image against the image size and the memory map of the part.
+///
+/// `handler` is called for every warning.
+#[cfg(feature = "std")]
+pub fn check_image_header<F: FnMut(String)>(
+    header: &ImageHeader,
+    firmware: &dyn Image,
+    part: Option<&Part>,
+    mut handler: F,
+) -> Result<()> {
+    let image_type = header
+        .image_type()
+        .ok_or(Error::InvalidBootHeader("unknown LPC5500 image type"))?;
+    let size = firmware.size();
+
+    // The signature of signed images follows the image length.
+    if header.image_length as usize != size && !image_type.is_signed() {
+        if image_type.is_crc() {
+            return Err(Error::InvalidBootHeader(
+                "image length doesn't match the image size",
+            ));
+        }
+
+        // Images built without header leave the reserved vectors cleared.
+        if header.image_length != 0 {
+            handler(format!(
+                "Image length {} bytes doesn't match the image size {} bytes",
+                header.image_length, size
+            ));
+        }
+    }
+
+    if image_type.is_xip() && header.load_address != firmware.base_address() {
+        handler(format!(
+            "Load address 0x{:08x} of an execute in place image isn't the image base address",
+            header.load_address
+        ));
+    }
+
+    let in_ram = |part: &Part| {
+        part.ram
+            .iter()
+            .any(|region| region.contains(header.load_address))
+    };
+
+    if !image_type.is_xip() && part.is_some_and(|part| !in_ram(part)) {
+        handler(format!(
+            "Load address 0x{:08x} of a RAM image outside RAM",
+            header.load_address
+        ));
+    }
+
+    Ok(())
+}
+
+/// Compute the CRC of a CRC image with the given patched vector table, the gaps between the data
+/// being erased flash, writing it in the header unless verifying.
+///
+/// Returns the CRC, and the mismatch to report once the checksum is verified.
+#[cfg(feature = "std")]
+pub fn update_crc(
+    firmware: &dyn Image,
+    vectors: &[u8],
+    header: &mut ImageHeader,
+    verify: bool,
+) -> Result<(u32, Option<Error>)> {
+    let mut data = firmware.read_all()?;
+    data[..vectors.len()].copy_from_slice(vectors);
+
+    let crc = compute_crc(&data)?;
+
+    if !verify {
+        header.specific_header = crc;
+    } else if header.specific_header != crc {
+        return Ok((
+            crc,
+            Some(Error::CrcMismatch {
+                expected: crc,
+                found: header.specific_header,
+            }),
+        ));
+    }
+
+    Ok((crc, None))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header_round_trip() {
+        let mut image = [0xA5; 0x100];
+        let header = ImageHeader {
+            image_length: 0x100,
+            image_type: ImageType::CrcXip.value() | 0x400,
+            specific_header: 0x1234_5678,
+            load_address: 0x1000,
+        };
+
+        header.write(&mut image).unwrap();
+        assert_eq!(
+            image[IMAGE_LENGTH_OFFSET..IMAGE_LENGTH_OFFSET + 4],
+            [0, 1, 0, 0]
+        );
+        assert_eq!(
+            image[LOAD_ADDRESS_OFFSET - 4..LOAD_ADDRESS_OFFSET],
+            [0xA5; 4]
+        );
+        assert_eq!(ImageHeader::parse(&image).unwrap(), header);
+        assert_eq!(header.image_type(), Some(ImageType::CrcXip));
+
+        assert!(matches!(
+            ImageHeader::parse(&image[..HEADER_SIZE - 1]),
+            Err(Error::ImageTooShort { .. })
+        ));
+    }
+
+    #[test]
+    fn crc_skips_crc_word() {
+        let mut image = (0..0x100).map(|i| i as u8).collect::<Vec<_>>();
+        let crc = compute_crc(&image).unwrap();
+
+        let mut expected = image.clone();
+        expected.drain(SPECIFIC_HEADER_OFFSET..SPECIFIC_HEADER_OFFSET + 4);
+        assert_eq!(crc, checksum::crc32(&expected));
+
+        // Writing the CRC in the header doesn't change it, while any other byte does.
+        image[SPECIFIC_HEADER_OFFSET..SPECIFIC_HEADER_OFFSET + 4]
+            .copy_from_slice(&crc.to_le_bytes());
+        assert_eq!(compute_crc(&image).unwrap(), crc);
+
+        image[SPECIFIC_HEADER_OFFSET + 4] ^= 1;
+        assert_ne!(compute_crc(&image).unwrap(), crc);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn update_and_verify_crc() {
+        let data = (0..0x100).map(|i| i as u8).collect::<Vec<_>>();
+        let mut firmware = crate::image::BinaryImage { data };
+        let mut header =
+            process_image_header(&mut firmware, Some(ImageType::CrcXip), None).unwrap();
+        assert_eq!(header.image_length, 0x100);
+        assert_eq!(header.load_address, 0);
+
+        let vectors = firmware.read_vec(0, HEADER_SIZE).unwrap();
+        let (crc, mismatch) = update_crc(&firmware, &vectors, &mut header, false).unwrap();
+        assert!(mismatch.is_none());
+        assert_eq!(header.specific_header, crc);
+
+        let mut data = firmware.read_all().unwrap();
+        header.write(&mut data).unwrap();
+        let firmware = crate::image::BinaryImage { data };
+
+        assert!(matches!(
+            update_crc(&firmware, &vectors, &mut header, true),
+            Ok((_, None))
+        ));
+
+        // A patched vector table changes the CRC.
+        let mut patched = vectors.clone();
+        patched[0] ^= 1;
+        assert!(matches!(
+            update_crc(&firmware, &patched, &mut header, true),
+            Ok((_, Some(Error::CrcMismatch { .. })))
+        ));
+    }
+}
@@ -1,113 +1,127 @@
-use clap::{App, Arg};
+use clap::{App, Arg, ArgMatches, ErrorKind};
 use env_logger::Builder;
 use log::{debug, error, info, warn, LevelFilter};
+use lpc_checksum::boot_header::{self, BootHeader, BLOCK_SIZE};
+use lpc_checksum::crp::{self, CrpLevel};
+#[cfg(feature = "secure")]
+use lpc_checksum::image::BinaryImage;
+use lpc_checksum::image::{self, Image};
+use lpc_checksum::lpc54;
+use lpc_checksum::lpc55::{self, ImageType};
+use lpc_checksum::part::{self, Family, Part, PARTS};
+#[cfg(feature = "secure")]
+use lpc_checksum::pfr::{self, Cfpa, Cmpa};
+#[cfg(feature = "secure")]
+use lpc_checksum::prince;
+use lpc_checksum::report::Report;
+#[cfg(feature = "secure")]
+use lpc_checksum::sb2::{self, Command, SecureBinary};
+use lpc_checksum::signature::{self, FlashSignature, SignatureWidth};
+#[cfg(feature = "secure")]
+use lpc_checksum::signed::{self, CertBlock};
+use lpc_checksum::vectors::{self, ArmInstruction};
+use lpc_checksum::Error;
+#[cfg(feature = "secure")]
+use rsa::RsaPrivateKey;
+#[cfg(feature = "secure")]
 use std::convert::TryInto;
 use std::env;
-use std::fs::{File, OpenOptions};
-use std::io::{Read, Seek, SeekFrom, Write};
-
-/// Structure used to define information needed to compute checksum on the various LPC processor.
-#[derive(Debug)]
-struct ProcessorChecksumInfo {
-    /// The name of the CPU familly.
-    cpu_family: &'static str,
-    /// The count of words used for checksum
-    words_count: Option<usize>,
-    /// The word position of the checksum value.
-    resulting_word_position: usize,
-}
-
-impl ProcessorChecksumInfo {
-    pub fn compute_checksum(&self, firmware_file: &mut File) -> std::io::Result<u32> {
-        let mut checksum = 0;
-        let mut buffer = Vec::new();
-        buffer.resize(self.words_count.unwrap() * std::mem::size_of::<u32>(), 0);
-
-        firmware_file.read_exact(&mut buffer)?;
-
-        let words = buffer
-            .chunks(4)
-            .map(|value| u32::from_le_bytes(value.try_into().unwrap()))
-            .collect::<Vec<u32>>();
-
-        for (i, word) in words.iter().enumerate() {
-            if i != self.resulting_word_position {
-                checksum += word;
-            }
-        }
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::process;
+
+/// Path used to designate the standard input or output.
+const STDIO_PATH: &str = "-";
+
+/// Modes of the boot image header, secure images being decrypted with the AES key.
+#[cfg(feature = "secure")]
+const BOOT_HEADER_MODES: &[&str] = &["prepend", "verify", "decrypt"];
+#[cfg(not(feature = "secure"))]
+const BOOT_HEADER_MODES: &[&str] = &["prepend", "verify"];
+
+/// Exit code used when the command line arguments are invalid.
+const EXIT_USAGE: i32 = 2;
+
+/// Get the exit code of the process for the given error.
+fn exit_code(error: &Error) -> i32 {
+    match error {
+        Error::ChecksumMismatch { .. } | Error::CrcMismatch { .. } => 1,
+        Error::Io(_) => 3,
+        Error::Usage(_) => EXIT_USAGE,
+        Error::InvalidImage(_) => 4,
+        Error::ImageTooShort { .. } => 5,
+        Error::UnsupportedFamily(_) => 6,
+        Error::UnknownProcessor => 7,
+        Error::PermanentCrp => 8,
+        Error::CrpLocationUsed(_) => 9,
+        Error::InvalidVectorTable(_) => 10,
+        Error::InvalidBootHeader(_) => 11,
+        #[cfg(feature = "secure")]
+        Error::InvalidSignature(_) => 12,
+        #[cfg(feature = "secure")]
+        Error::InvalidProtectedPage(_) => 13,
+        #[cfg(feature = "secure")]
+        Error::InvalidSecureBinary(_) => 14,
+        #[cfg(feature = "secure")]
+        Error::InvalidPrince(_) => 15,
+    }
+}
 
-        Ok(0u32.overflowing_sub(checksum).0)
-    }
-}
-
-static PROCESSOR_CHECKSUM: &[ProcessorChecksumInfo] = &[
-    // LPC3 doesn't suppoort checksum validation.
-    ProcessorChecksumInfo {
-        cpu_family: "LPC3",
-        words_count: None,
-        resulting_word_position: 0,
-    },
-    // LPC29 doesn't suppoort checksum validation.
-    ProcessorChecksumInfo {
-        cpu_family: "LPC29",
-        words_count: None,
-        resulting_word_position: 0,
-    },
-    ProcessorChecksumInfo {
-        cpu_family: "LPC1",
-        words_count: Some(7),
-        resulting_word_position: 7,
-    },
-    ProcessorChecksumInfo {
-        cpu_family: "LPC2",
-        words_count: Some(8),
-        resulting_word_position: 5,
-    },
-    ProcessorChecksumInfo {
-        cpu_family: "LPC4",
-        words_count: Some(7),
-        resulting_word_position: 7,
-    },
-    ProcessorChecksumInfo {
-        cpu_family: "LPC5",
-        words_count: Some(7),
-        resulting_word_position: 7,
-    },
-];
-
-fn get_processor_checksum_info_by_name(cpu_part_number: &str) -> Option<&ProcessorChecksumInfo> {
-    for processor in PROCESSOR_CHECKSUM {
-        if cpu_part_number.contains(processor.cpu_family) {
-            return Some(processor);
-        }
-    }
-
-    None
-}
-
-fn main() -> std::io::Result<()> {
+fn main() {
     env::set_var("RUST_LOG", "debug");
     let mut builder = Builder::from_default_env();
     builder.format_timestamp(None);
 
-    let matches = App::new("LPC BootROM checksum calculator")
+    let app = App::new("LPC BootROM checksum calculator")
         .version("1.0")
         .author("Mary")
         .about("Handle LPC BootROM checksum calculation for various LPC processor.")
+        .after_help(
+            "EXIT CODES:\n    \
+             0    Success\n    \
+             1    Checksum or CRC mismatch\n    \
+             2    Invalid arguments\n    \
+             3    I/O error\n    \
+             4    Invalid image file\n    \
+             5    Image too short\n    \
+             6    Checksum not supported by the processor familly\n    \
+             7    Unknown processor\n    \
+             8    CRP3 image patched without --allow-crp3\n    \
+             9    CRP word location used by code\n    \
+             10   Invalid vector table\n    \
+             11   Invalid boot image header\n    \
+             12   Invalid signature or certificate\n    \
+             13   Invalid protected flash page\n    \
+             14   Invalid secure binary file\n    \
+             15   Invalid PRINCE configuration",
+        )
         .arg(
             Arg::with_name("processor")
                 .short("p")
                 .long("processor")
                 .value_name("PROCESSOR")
-                .default_value("LPC1000")
-                .help("Define the processor used (e.g. LPC1768, or LPC2103)"),
+                .default_value("LPC1700")
+                .help("Define the processor part number (e.g. LPC1768, or LPC2103) or familly (e.g. LPC1700)"),
         )
         .arg(
             Arg::with_name("INPUT")
-                .help("Sets the input file to use")
-                .required(true)
+                .help("Sets the input file to use, or - for the standard input")
+                .required_unless("list")
                 .index(1),
         )
+        .arg(
+            Arg::with_name("list")
+                .short("l")
+                .long("list")
+                .help("List the known processors"),
+        )
+        .arg(
+            Arg::with_name("output")
+                .short("o")
+                .long("output")
+                .value_name("OUTPUT")
+                .help("Write the patched image to this file instead of the input, or - for the standard output"),
+        )
         .arg(
             Arg::with_name("verbose")
                 .short("v")
@@ -120,19 +134,228 @@ fn main() -> std::io::Result<()> {
                 .long("display")
                 .help("Display operation done"),
         )
+        .arg(
+            Arg::with_name("verify")
+                .short("c")
+                .long("verify")
+                .help("Check the existing checksum value instead of writing it"),
+        )
+        .arg(
+            Arg::with_name("format")
+                .short("f")
+                .long("format")
+                .value_name("FORMAT")
+                .possible_values(&["text", "json"])
+                .default_value("text")
+                .help("Define the format of the report printed on the standard output"),
+        )
         .arg(
             Arg::with_name("dry-run")
                 .short("n")
                 .long("dry-run")
                 .help("Do not write the checksum value"),
         )
-        .get_matches();
+        .arg(
+            Arg::with_name("set-crp")
+                .long("set-crp")
+                .value_name("LEVEL")
+                .possible_values(&["none", "no_isp", "crp1", "crp2", "crp3"])
+                .case_insensitive(true)
+                .conflicts_with("verify")
+                .help("Write the Code Read Protection level before computing the checksum"),
+        )
+        .arg(
+            Arg::with_name("allow-crp3")
+                .long("allow-crp3")
+                .help("Allow patching an image enabling CRP3, which permanently disables ISP"),
+        )
+        .arg(
+            Arg::with_name("force")
+                .long("force")
+                .help("Patch the image even if the vector table doesn't match the processor, or prepend a boot image header to an image already having one"),
+        )
+        .arg(
+            Arg::with_name("boot-header")
+                .long("boot-header")
+                .value_name("MODE")
+                .possible_values(BOOT_HEADER_MODES)
+                .requires_if("decrypt", "output")
+                .help("Prepend or verify the LPC1800/LPC4300 boot image header of a binary image, or remove it from a decrypted secure image"),
+        )
+        .arg(
+            Arg::with_name("image-type")
+                .long("image-type")
+                .value_name("TYPE")
+                .possible_values(&["plain", "crc-ram", "crc-xip", "signed", "signed-xip"])
+                .conflicts_with("verify")
+                .help("Write the LPC5500 image header with this image type, computing the CRC of CRC images and signing signed images"),
+        )
+        .arg(
+            Arg::with_name("load-address")
+                .long("load-address")
+                .value_name("ADDRESS")
+                .validator(|value| {
+                    parse_address(&value)
+                        .map(|_| ())
+                        .ok_or_else(|| format!("Invalid address \"{}\"", value))
+                })
+                .help("Define the load address of the LPC5400 or LPC5500 image, the image base address by default"),
+        )
+        .arg(
+            Arg::with_name("flash-signature")
+                .long("flash-signature")
+                .help("Compute the flash signature of the patched image, as read by the Read flash signature ISP and IAP commands"),
+        )
+        .arg(
+            Arg::with_name("signature-range")
+                .long("signature-range")
+                .value_name("START:END")
+                .validator(|value| {
+                    parse_range(&value)
+                        .map(|_| ())
+                        .ok_or_else(|| format!("Invalid address range \"{}\"", value))
+                })
+                .help("Compute the flash signature from the START address to the END address excluded instead of the whole image, the flash outside the image being erased"),
+        )
+        .arg(
+            Arg::with_name("boot-block")
+                .long("boot-block")
+                .value_name("TYPE")
+                .possible_values(&["xip", "ram"])
+                .conflicts_with("verify")
+                .help("Append a LPC5400 enhanced boot block of this image type to a binary image"),
+        );
+
+    #[cfg(feature = "secure")]
+    let app = app
+        .arg(
+            Arg::with_name("aes-key")
+                .long("aes-key")
+                .value_name("KEY")
+                .requires("boot-header")
+                .help("Encrypt the LPC18Sxx/LPC43Sxx image with this AES user key when prepending the boot image header, or check and decrypt the secure image, as 16 raw bytes or 32 hexadecimal digits"),
+        )
+        .arg(
+            Arg::with_name("sign-key")
+                .long("sign-key")
+                .value_name("KEY")
+                .requires("certificate")
+                .conflicts_with("verify")
+                .help("Sign the LPC5500 binary image with this RSA private key, in PEM format"),
+        )
+        .arg(
+            Arg::with_name("certificate")
+                .long("certificate")
+                .value_name("CERTIFICATE")
+                .multiple(true)
+                .number_of_values(1)
+                .requires("sign-key")
+                .help("Add a certificate to the chain, from the root certificate to the one of the signing key, in PEM or DER format"),
+        )
+        .arg(
+            Arg::with_name("root-key")
+                .long("root-key")
+                .value_name("KEY")
+                .multiple(true)
+                .number_of_values(1)
+                .max_values(4)
+                .requires("sign-key")
+                .help("Add a public key or certificate to the root key table, only holding the root certificate key by default"),
+        )
+        .arg(
+            Arg::with_name("root-key-hash")
+                .long("root-key-hash")
+                .value_name("HASH")
+                .validator(|value| {
+                    parse_hash(&value)
+                        .map(|_| ())
+                        .ok_or_else(|| format!("Invalid SHA-256 hash \"{}\"", value))
+                })
+                .help("Check the root key table hash of the LPC5500 signed image, as programmed in the CMPA"),
+        )
+        .arg(
+            Arg::with_name("inspect")
+                .long("inspect")
+                .conflicts_with_all(&["sign-key", "image-type"])
+                .help("Print the LPC5500 image header, certificate block and signature, without writing the image"),
+        )
+        .arg(
+            Arg::with_name("protected-page")
+                .long("protected-page")
+                .value_name("PAGE")
+                .possible_values(&["cmpa", "cfpa"])
+                .conflicts_with_all(&[
+                    "sign-key",
+                    "inspect",
+                    "image-type",
+                    "boot-header",
+                    "boot-block",
+                    "set-crp",
+                ])
+                .help("Build the LPC55S6x CMPA or CFPA page from the TOML or JSON description in the input file, or decode the page dump with --verify"),
+        )
+        .arg(
+            Arg::with_name("sb-file")
+                .long("sb-file")
+                .value_name("FILE")
+                .requires("sb-kek")
+                .conflicts_with_all(&["inspect", "protected-page"])
+                .help("Also write the patched LPC5500 image as a SB2.1 file erasing the flash and loading it, signed with the image, or check this SB2.1 file with --verify"),
+        )
+        .arg(
+            Arg::with_name("sb-kek")
+                .long("sb-kek")
+                .value_name("KEY")
+                .requires("sb-file")
+                .help("Define the SBKEK wrapping the SB2.1 file keys, as 32 raw bytes or 64 hexadecimal digits"),
+        )
+        .arg(
+            Arg::with_name("sb-jump")
+                .long("sb-jump")
+                .requires("sb-file")
+                .conflicts_with("verify")
+                .help("End the SB2.1 file with a jump to the reset handler of the image"),
+        )
+        .arg(
+            Arg::with_name("prince-regions")
+                .long("prince-regions")
+                .value_name("CMPA")
+                .requires("prince-key")
+                .conflicts_with("protected-page")
+                .help("Encrypt the patched LPC55S6x image with the PRINCE regions enabled by this CMPA page or its TOML or JSON description, or decrypt it with --verify"),
+        )
+        .arg(
+            Arg::with_name("prince-key")
+                .long("prince-key")
+                .value_name("KEY")
+                .multiple(true)
+                .number_of_values(1)
+                .max_values(3)
+                .requires("prince-regions")
+                .help("Add the key and IV of the next enabled PRINCE region, as 24 raw bytes or 48 hexadecimal digits"),
+        )
+        .arg(
+            Arg::with_name("prince-decrypt")
+                .long("prince-decrypt")
+                .requires_all(&["prince-regions", "output"])
+                .help("Decrypt the PRINCE encrypted input image before processing it, writing it decrypted to the output"),
+        );
+
+    let matches = app
+        .get_matches_safe()
+        .unwrap_or_else(|error| match error.kind {
+            ErrorKind::HelpDisplayed | ErrorKind::VersionDisplayed => error.exit(),
+            _ => {
+                eprintln!("{}", error.message);
+                process::exit(EXIT_USAGE);
+            }
+        });
 
     let verbose = matches.is_present("verbose");
     let display = matches.is_present("display");
 
     if !display {
-        builder.filter(None, LevelFilter::Off);
+        builder.filter(None, LevelFilter::Error);
     }
 
     if verbose {
@@ -143,45 +366,1329 @@ fn main() -> std::io::Result<()> {
 
     builder.init();
 
+    if let Err(error) = run(&matches) {
+        error!("{}", error);
+        process::exit(exit_code(&error));
+    }
+}
+
+/// Parse an address, in hexadecimal with a 0x prefix or in decimal.
+fn parse_address(value: &str) -> Option<u32> {
+    match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(value) => u32::from_str_radix(value, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+/// Parse an address range, as the start and end addresses separated by a colon.
+fn parse_range(value: &str) -> Option<(u32, u32)> {
+    let (start, end) = value.split_once(':')?;
+
+    Some((parse_address(start)?, parse_address(end)?))
+}
+
+/// Parse bytes in hexadecimal.
+#[cfg(feature = "secure")]
+fn parse_hex(value: &str) -> Option<Vec<u8>> {
+    if !value.len().is_multiple_of(2) || !value.is_ascii() {
+        return None;
+    }
+
+    (0..value.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&value[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// Parse a SHA-256 hash in hexadecimal.
+#[cfg(feature = "secure")]
+fn parse_hash(value: &str) -> Option<signed::Hash> {
+    parse_hex(value)?.try_into().ok()
+}
+
+/// Read the whole input file, or the standard input.
+fn read_input(input: &str) -> io::Result<Vec<u8>> {
+    if input == STDIO_PATH {
+        let mut data = Vec::new();
+        io::stdin().lock().read_to_end(&mut data)?;
+
+        return Ok(data);
+    }
+
+    fs::read(input)
+}
+
+/// Write the image to the output file, or the standard output, after its boot image header.
+fn write_output(
+    output: &str,
+    boot_header: Option<&BootHeader>,
+    firmware: &dyn Image,
+) -> io::Result<()> {
+    let mut writer: Box<dyn Write> = if output == STDIO_PATH {
+        Box::new(io::stdout().lock())
+    } else {
+        Box::new(File::create(output)?)
+    };
+
+    if let Some(boot_header) = boot_header {
+        writer.write_all(&boot_header.to_bytes())?;
+    }
+
+    firmware.save(&mut writer)
+}
+
+/// Record a warning about the image, logging it.
+fn record_warning(warnings: &mut Vec<String>, warning: String) {
+    warn!("{}", warning);
+    warnings.push(warning);
+}
+
+/// Print the known processors with their main characteristics.
+fn list_parts() {
+    for part in PARTS {
+        let boot_rom = &part.boot_rom;
+        let isp = [
+            (boot_rom.uart_isp, "UART"),
+            (boot_rom.usb_isp, "USB"),
+            (boot_rom.can_isp, "CAN"),
+        ]
+        .iter()
+        .filter(|(supported, _)| *supported)
+        .map(|(_, interface)| *interface)
+        .collect::<Vec<_>>();
+
+        println!(
+            "{:<10} {:<8} {:<11} flash {:>4} KB  RAM {:>4} KB  ISP {}",
+            part.name,
+            part.family.name(),
+            part.core.name(),
+            part.flash_size() / 1024,
+            part.ram_size() / 1024,
+            if isp.is_empty() {
+                "none".to_string()
+            } else {
+                isp.join("/")
+            }
+        );
+    }
+}
+
+/// Format a flash signature in hexadecimal, with all the digits of its width.
+fn format_signature(flash_signature: &FlashSignature) -> String {
+    format!(
+        "0x{:0width$x}",
+        flash_signature.value,
+        width = flash_signature.bits as usize / 4
+    )
+}
+
+/// Load the signing key and build the certificate block of the LPC5500 signed image.
+#[cfg(feature = "secure")]
+fn load_signer(matches: &ArgMatches) -> Result<Option<(RsaPrivateKey, CertBlock)>, Error> {
+    let path = match matches.value_of("sign-key") {
+        Some(path) => path,
+        None => return Ok(None),
+    };
+    let read = |path: &str| fs::read(path).inspect_err(|_| error!("Cannot open file {}", path));
+
+    let key = signed::load_private_key(&read(path)?)?;
+    let certificates = matches
+        .values_of("certificate")
+        .unwrap()
+        .map(|path| signed::load_certificate(&read(path)?))
+        .collect::<Result<Vec<_>, Error>>()?;
+    let root_keys = matches
+        .values_of("root-key")
+        .into_iter()
+        .flatten()
+        .map(|path| signed::load_public_key(&read(path)?))
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let cert_block = CertBlock::new(certificates, &root_keys)?;
+    cert_block.check_signing_key(&key)?;
+
+    Ok(Some((key, cert_block)))
+}
+
+/// Print the LPC5500 image header, and the certificate block and signature of signed images.
+#[cfg(feature = "secure")]
+fn print_inspection(report: &Report, root_key_hash: Option<signed::Hash>) {
+    let image_header = match &report.image_header {
+        Some(image_header) => image_header,
+        None => return,
+    };
+    let image_type = image_header.image_type();
+
+    println!("Image header:");
+    println!(
+        "    Image length:         {} bytes",
+        image_header.image_length
+    );
+    println!(
+        "    Image type:           {} (0x{:08x})",
+        image_type.map_or("unknown", ImageType::name),
+        image_header.image_type
+    );
+    println!(
+        "    {:<21} 0x{:08x}",
+        match image_type {
+            Some(image_type) if image_type.is_crc() => "CRC:",
+            Some(image_type) if image_type.is_signed() => "Certificate block:",
+            _ => "Specific header:",
+        },
+        image_header.specific_header
+    );
+    println!(
+        "    Load address:         0x{:08x}",
+        image_header.load_address
+    );
+
+    let inspection = match &report.inspection {
+        Some(inspection) => inspection,
+        None => return,
+    };
+    let cert_block = &inspection.cert_block;
+
+    println!("Certificate block:");
+    println!(
+        "    Version:              {}.{}",
+        cert_block.major_version, cert_block.minor_version
+    );
+    println!("    Header size:          {} bytes", cert_block.header_size);
+    println!("    Flags:                0x{:08x}", cert_block.flags);
+    println!("    Build number:         {}", cert_block.build_number);
+    println!(
+        "    Total image length:   {} bytes",
+        cert_block.total_image_length
+    );
+    println!(
+        "    Certificates:         {} ({} bytes)",
+        cert_block.certificate_count, cert_block.certificate_table_length
+    );
+
+    for (i, certificate) in inspection.certificates.iter().enumerate() {
+        println!("Certificate {}:", i);
+        println!("    Subject:              {}", certificate.subject);
+        println!("    Issuer:               {}", certificate.issuer);
+        println!("    Serial number:        {}", certificate.serial_number);
+        println!(
+            "    Validity:             {} to {}",
+            certificate.not_before, certificate.not_after
+        );
+        println!(
+            "    Key:                  RSA {} bits",
+            certificate.key_size
+        );
+        println!("    Key hash:             {}", certificate.key_hash);
+    }
+
+    println!("Root key table:");
+
+    for (i, hash) in inspection.root_key_hashes.iter().enumerate() {
+        println!(
+            "    {}: {}{}",
+            i,
+            hash,
+            if inspection.root_key_index == Some(i) {
+                " (root certificate)"
+            } else {
+                ""
+            }
+        );
+    }
+
+    println!(
+        "Root key table hash:      {}",
+        inspection.root_key_table_hash
+    );
+
+    if let Some(hash) = root_key_hash {
+        println!(
+            "    {} the CMPA value {}",
+            if signed::to_hex(&hash) == inspection.root_key_table_hash {
+                "Matches"
+            } else {
+                "Doesn't match"
+            },
+            signed::to_hex(&hash)
+        );
+    }
+
+    println!(
+        "Certificate chain:        {}",
+        inspection.chain_error.as_deref().unwrap_or("valid")
+    );
+    println!(
+        "Signature:                {} bytes, {}",
+        inspection.signature.len() / 2,
+        inspection.signature_error.as_deref().unwrap_or("valid")
+    );
+
+    for line in inspection.signature.as_bytes().chunks(64) {
+        println!("    {}", String::from_utf8_lossy(line));
+    }
+}
+
+/// Load a key from a file holding its raw bytes or their hexadecimal digits.
+///
+/// Returns `None` if the file doesn't hold a key of the given size.
+#[cfg(feature = "secure")]
+fn load_key<const N: usize>(path: &str) -> Result<Option<[u8; N]>, Error> {
+    let data = fs::read(path).inspect_err(|_| error!("Cannot open file {}", path))?;
+
+    if let Ok(key) = data.as_slice().try_into() {
+        return Ok(Some(key));
+    }
+
+    Ok(core::str::from_utf8(&data)
+        .ok()
+        .and_then(|text| parse_hex(text.trim()))
+        .and_then(|key| key.try_into().ok()))
+}
+
+/// Load the SBKEK, from a file holding 32 raw bytes or 64 hexadecimal digits.
+#[cfg(feature = "secure")]
+fn load_sb_kek(path: &str) -> Result<[u8; sb2::KEY_SIZE], Error> {
+    load_key(path)?.ok_or_else(|| {
+        Error::InvalidSecureBinary(format!(
+            "SBKEK {} isn't 32 bytes or 64 hexadecimal digits",
+            path
+        ))
+    })
+}
+
+/// Load the AES user key of the LPC18Sxx/LPC43Sxx, from a file holding 16 raw bytes or 32
+/// hexadecimal digits.
+#[cfg(feature = "secure")]
+fn load_aes_key(path: &str) -> Result<[u8; boot_header::KEY_SIZE], Error> {
+    load_key(path)?.ok_or_else(|| {
+        error!("Invalid key file {}", path);
+
+        Error::InvalidBootHeader("AES key isn't 16 bytes or 32 hexadecimal digits")
+    })
+}
+
+/// Check the SB2.1 file loading the image, and its root key table hash if given and signed.
+#[cfg(feature = "secure")]
+fn verify_secure_binary(
+    path: &str,
+    firmware: &dyn Image,
+    kek: [u8; sb2::KEY_SIZE],
+    root_key_hash: Option<signed::Hash>,
+) -> Result<(), Error> {
+    let data = fs::read(path).inspect_err(|_| error!("Cannot open file {}", path))?;
+    let (secure_binary, _) = SecureBinary::parse(&data, kek)?;
+
+    for section in &secure_binary.sections {
+        info!("SB2.1 section {}:", section.id);
+
+        for command in &section.commands {
+            match command {
+                Command::Erase { address, length } => {
+                    info!("    erase 0x{:08x}, {} bytes", address, length)
+                }
+                Command::EraseAll => info!("    erase all"),
+                Command::Load { address, data } => {
+                    info!("    load 0x{:08x}, {} bytes", address, data.len())
+                }
+                Command::Jump {
+                    address,
+                    argument,
+                    stack_pointer,
+                } => match stack_pointer {
+                    Some(stack_pointer) => info!(
+                        "    jump 0x{:08x}, argument 0x{:08x}, stack pointer 0x{:08x}",
+                        address, argument, stack_pointer
+                    ),
+                    None => info!("    jump 0x{:08x}, argument 0x{:08x}", address, argument),
+                },
+                Command::Reset => info!("    reset"),
+            }
+        }
+    }
+
+    secure_binary.check_image(firmware, root_key_hash)
+}
+
+/// Load the PRINCE regions enabled by the CMPA page or description, with the keys and IVs of the
+/// key files given in the region order.
+#[cfg(feature = "secure")]
+fn load_prince_regions(matches: &ArgMatches) -> Result<Option<Vec<prince::Region>>, Error> {
+    let path = match matches.value_of("prince-regions") {
+        Some(path) => path,
+        None => return Ok(None),
+    };
+    let cmpa = Cmpa::load(&fs::read(path).inspect_err(|_| error!("Cannot open file {}", path))?)?;
+    let keys = matches
+        .values_of("prince-key")
+        .unwrap()
+        .map(|path| {
+            load_key(path)?.ok_or_else(|| {
+                error!("Invalid key file {}", path);
+
+                Error::InvalidPrince("key file isn't 24 bytes or 48 hexadecimal digits")
+            })
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    let regions = prince::enabled_regions(&cmpa, &keys)?;
+
+    for region in &regions {
+        info!(
+            "PRINCE region {}: 0x{:08x}, subregions 0x{:08x}",
+            region.index, region.base_address, region.subregions
+        );
+    }
+
+    Ok(Some(regions))
+}
+
+/// Format the description of a protected flash region page, in TOML or in JSON.
+#[cfg(feature = "secure")]
+fn describe_page<T: serde::Serialize>(page: &T, json: bool) -> String {
+    if json {
+        serde_json::to_string_pretty(page).unwrap() + "\n"
+    } else {
+        toml::to_string(page).unwrap()
+    }
+}
+
+/// Build a protected flash region page from its description, or decode a page dump.
+#[cfg(feature = "secure")]
+fn run_protected_page(matches: &ArgMatches, page: &str) -> Result<(), Error> {
     let processor = matches.value_of("processor").unwrap();
     let input = matches.value_of("INPUT").unwrap();
-    let dry_run = matches.is_present("dry-run");
+    let json = matches.value_of("format") == Some("json");
+    let family = part::get_part_by_name(processor)
+        .map(|part| part.family)
+        .or_else(|| Family::from_name(processor))
+        .or_else(|| Family::from_part_number(processor));
+
+    if matches.occurrences_of("processor") != 0 && family != Some(Family::Lpc5500) {
+        return Err(Error::Usage(
+            "Protected flash pages only apply to LPC5500 parts".to_string(),
+        ));
+    }
+
+    let data = read_input(input).inspect_err(|_| error!("Cannot open file {}", input))?;
+
+    if matches.is_present("verify") {
+        let description = match page {
+            "cmpa" => describe_page(&Cmpa::parse(&data)?, json),
+            _ => describe_page(&Cfpa::parse(&data)?, json),
+        };
+        print!("{}", description);
+
+        return Ok(());
+    }
+
+    let output = matches.value_of("output").ok_or_else(|| {
+        Error::Usage("Building a protected flash page needs an output file".to_string())
+    })?;
+    let description = String::from_utf8(data)
+        .map_err(|_| Error::InvalidProtectedPage("description isn't UTF-8 text".to_string()))?;
+    let data = match page {
+        "cmpa" => {
+            let cmpa: Cmpa = pfr::parse_description(&description)?;
+
+            if cmpa.seal {
+                warn!("Sealed CMPA page, permanently locked once programmed");
+            }
+
+            cmpa.to_bytes()?
+        }
+        _ => pfr::parse_description::<Cfpa>(&description)?.to_bytes()?,
+    };
+
+    info!(
+        "{} page at 0x{:05x}, digest {}",
+        page.to_uppercase(),
+        if page == "cmpa" {
+            pfr::CMPA_ADDRESS
+        } else {
+            pfr::CFPA_ADDRESS
+        },
+        signed::to_hex(&data[pfr::DIGEST_OFFSET..])
+    );
+
+    if !matches.is_present("dry-run") {
+        write_output(
+            output,
+            None,
+            &BinaryImage {
+                data: data.to_vec(),
+            },
+        )
+        .inspect_err(|_| error!("Cannot write file {}", output))?;
+    }
+
+    Ok(())
+}
+
+/// Options of the operation on an image, parsed from the command line.
+struct Options<'a> {
+    input: &'a str,
+    output: &'a str,
+    inspect: bool,
+    dry_run: bool,
+    verify: bool,
+    allow_crp3: bool,
+    force: bool,
+    set_crp: Option<CrpLevel>,
+    json: bool,
+    boot_header_mode: Option<&'a str>,
+    signing: bool,
+    image_type: Option<ImageType>,
+    load_address: Option<u32>,
+    flash_signature: bool,
+    signature_range: Option<(u32, u32)>,
+    boot_block_type: Option<u32>,
+    #[cfg(feature = "secure")]
+    root_key_hash: Option<signed::Hash>,
+    #[cfg(feature = "secure")]
+    sb_file: Option<&'a str>,
+    #[cfg(feature = "secure")]
+    sb_kek: Option<[u8; sb2::KEY_SIZE]>,
+    #[cfg(feature = "secure")]
+    sb_jump: bool,
+    #[cfg(feature = "secure")]
+    aes_key: Option<[u8; boot_header::KEY_SIZE]>,
+    #[cfg(feature = "secure")]
+    prince_regions: Option<Vec<prince::Region>>,
+    #[cfg(feature = "secure")]
+    prince_decrypt: bool,
+}
+
+impl<'a> Options<'a> {
+    /// Parse the options of the command line, loading the key files.
+    fn parse(matches: &'a ArgMatches) -> Result<Self, Error> {
+        let input = matches.value_of("INPUT").unwrap();
+        #[cfg(feature = "secure")]
+        let inspect = matches.is_present("inspect");
+        #[cfg(not(feature = "secure"))]
+        let inspect = false;
+        #[cfg(feature = "secure")]
+        let signing = matches.is_present("sign-key");
+        #[cfg(not(feature = "secure"))]
+        let signing = false;
+        let verify = matches.is_present("verify");
+        let signature_range = matches.value_of("signature-range").and_then(parse_range);
+
+        Ok(Options {
+            input,
+            output: matches.value_of("output").unwrap_or(input),
+            inspect,
+            dry_run: matches.is_present("dry-run") || inspect,
+            verify,
+            allow_crp3: matches.is_present("allow-crp3"),
+            force: matches.is_present("force"),
+            set_crp: matches
+                .value_of("set-crp")
+                .map(|level| CrpLevel::from_name(level).unwrap()),
+            json: matches.value_of("format") == Some("json"),
+            boot_header_mode: matches.value_of("boot-header"),
+            signing,
+            image_type: matches
+                .value_of("image-type")
+                .map(|image_type| ImageType::from_name(image_type).unwrap())
+                .or(Some(ImageType::SignedXip).filter(|_| signing)),
+            load_address: matches.value_of("load-address").and_then(parse_address),
+            flash_signature: matches.is_present("flash-signature") || signature_range.is_some(),
+            signature_range,
+            boot_block_type: matches.value_of("boot-block").map(|boot_block_type| {
+                match boot_block_type {
+                    "xip" => lpc54::IMAGE_TYPE_XIP,
+                    _ => lpc54::IMAGE_TYPE_RAM,
+                }
+            }),
+            #[cfg(feature = "secure")]
+            root_key_hash: matches.value_of("root-key-hash").and_then(parse_hash),
+            #[cfg(feature = "secure")]
+            sb_file: matches.value_of("sb-file"),
+            #[cfg(feature = "secure")]
+            sb_kek: matches.value_of("sb-kek").map(load_sb_kek).transpose()?,
+            #[cfg(feature = "secure")]
+            sb_jump: matches.is_present("sb-jump"),
+            #[cfg(feature = "secure")]
+            aes_key: matches.value_of("aes-key").map(load_aes_key).transpose()?,
+            #[cfg(feature = "secure")]
+            prince_regions: load_prince_regions(matches)?,
+            #[cfg(feature = "secure")]
+            prince_decrypt: matches.is_present("prince-decrypt") || verify,
+        })
+    }
+
+    /// Check that the options apply to the familly and part.
+    ///
+    /// Returns the width of the flash signature generator, if any.
+    fn check(&self, family: Family, part: Option<&Part>) -> Result<Option<SignatureWidth>, Error> {
+        let usage = |message: &str| Err(Error::Usage(message.to_string()));
+
+        if self.json && self.output == STDIO_PATH && !self.dry_run && !self.verify {
+            return usage("Cannot write both the image and the JSON report on the standard output");
+        }
+
+        if self.boot_header_mode.is_some() && family != Family::Lpc1800 && family != Family::Lpc4300
+        {
+            return usage(&format!(
+                "{} doesn't use a boot image header",
+                family.name()
+            ));
+        }
+
+        #[cfg(feature = "secure")]
+        if self.boot_header_mode == Some("decrypt") && self.aes_key.is_none() {
+            return usage("Decrypting a secure image needs the AES key");
+        }
+
+        #[cfg(feature = "secure")]
+        if (self.boot_header_mode == Some("decrypt") || self.prince_decrypt && !self.verify)
+            && !self.dry_run
+            && self.output == self.input
+        {
+            return usage("Decrypted images can't be written back to the encrypted input");
+        }
+
+        if self.image_type.is_some() && family != Family::Lpc5500 {
+            return usage(&format!("{} doesn't use an image type", family.name()));
+        }
+
+        if self.image_type.is_some_and(ImageType::is_signed) && !self.signing {
+            return usage("Signed image types need a signing key");
+        }
+
+        if self.signing && !self.image_type.is_some_and(ImageType::is_signed) {
+            return usage("The signing key only applies to signed image types");
+        }
+
+        if self.inspect && family != Family::Lpc5500 {
+            return usage("Only LPC5500 images can be inspected");
+        }
+
+        #[cfg(feature = "secure")]
+        if self.root_key_hash.is_some() && !self.verify && !self.inspect {
+            return usage("The root key hash only applies when verifying or inspecting");
+        }
+
+        #[cfg(feature = "secure")]
+        if self.prince_regions.is_some() && family != Family::Lpc5500 {
+            return usage("Only LPC5500 images can be PRINCE encrypted");
+        }
+
+        #[cfg(feature = "secure")]
+        if self.sb_file.is_some() && family != Family::Lpc5500 {
+            return usage("Only LPC5500 images can be written as SB2.1 files");
+        }
+
+        if self.set_crp.is_some() && crp_offset(family, part).is_none() {
+            return usage(&format!("{} doesn't use a CRP word", family.name()));
+        }
+
+        // Flashless parts don't have a flash signature generator.
+        let signature_width = family
+            .flash_signature()
+            .filter(|_| part.is_none_or(|part| part.boot_rom.flash_signature));
 
-    let mut processor_info_opt = get_processor_checksum_info_by_name(processor);
+        if self.flash_signature && signature_width.is_none() {
+            return usage(&format!(
+                "{} doesn't have a flash signature generator",
+                family.name()
+            ));
+        }
+
+        if let (Some((start, end)), Some(width)) = (self.signature_range, signature_width) {
+            let word_size = width.word_size() as u32;
+
+            if start >= end || !start.is_multiple_of(word_size) || !end.is_multiple_of(word_size) {
+                return usage(&format!(
+                    "The flash signature range must hold {} bytes flash words",
+                    word_size
+                ));
+            }
+        }
 
-    if processor_info_opt.is_none() {
-        warn!(
-            "Unknown processor \"{}\", falling back to LPC1000",
-            processor
+        if self.boot_block_type.is_some() && family != Family::Lpc5400 {
+            return usage(&format!(
+                "{} doesn't use an enhanced boot block",
+                family.name()
+            ));
+        }
+
+        if self.load_address.is_some() && self.image_type.is_none() && family != Family::Lpc5400 {
+            return usage("The load address only applies to LPC5400 images or with an image type");
+        }
+
+        Ok(signature_width)
+    }
+}
+
+/// Get the offset of the CRP word read by the boot ROM of the familly and part, if any.
+fn crp_offset(family: Family, part: Option<&Part>) -> Option<usize> {
+    // Flashless parts boot from external memories and don't read a CRP word.
+    crp::crp_offset(family).filter(|_| part.is_none_or(|part| part.boot_rom.crp))
+}
+
+/// Parse and check the LPC1800/LPC4300 boot image header at the start of the image, removing it
+/// and decrypting secure images, or create the header to prepend to the image.
+fn read_boot_header(
+    data: &mut Vec<u8>,
+    options: &Options,
+    warnings: &mut Vec<String>,
+) -> Result<Option<BootHeader>, Error> {
+    let boot_header = match options.boot_header_mode {
+        Some("verify") | Some("decrypt") => boot_header::strip(
+            data,
+            #[cfg(feature = "secure")]
+            options.aes_key.as_ref(),
+        )?,
+        Some(_) => boot_header::create(data, options.force, |warning| {
+            record_warning(warnings, warning)
+        })?,
+        None => return Ok(None),
+    };
+
+    info!(
+        "Boot header: AES {}, hash {}, {} blocks ({} bytes), hash value 0x{:016x}",
+        if boot_header.aes_active {
+            "active"
+        } else {
+            "not active"
+        },
+        if boot_header.hash_active {
+            "active"
+        } else {
+            "not active"
+        },
+        boot_header.hash_size,
+        boot_header.image_size(),
+        boot_header.hash_value
+    );
+
+    if boot_header.image_size() - data.len() >= BLOCK_SIZE {
+        record_warning(
+            warnings,
+            format!(
+                "Boot image header loads {} bytes for a {} bytes image",
+                boot_header.image_size(),
+                data.len()
+            ),
         );
+    }
+
+    Ok(Some(boot_header))
+}
 
-        processor_info_opt = get_processor_checksum_info_by_name("LPC1000");
+/// Write the CRP level if given, then read the CRP level of the image.
+fn process_crp(
+    firmware: &mut dyn Image,
+    family: Family,
+    part: Option<&Part>,
+    set_crp: Option<CrpLevel>,
+) -> Result<Option<CrpLevel>, Error> {
+    let crp_offset = crp_offset(family, part);
+
+    if let (Some(level), Some(offset)) = (set_crp, crp_offset) {
+        let mut word = firmware.read_vec(offset, 4)?;
+
+        crp::write_crp(&mut word, 0, level)?;
+        firmware.write(offset, &word)?;
     }
 
-    let processor_info = processor_info_opt.unwrap();
+    let crp_level = crp_offset
+        .and_then(|offset| firmware.read_vec(offset, 4).ok())
+        .and_then(|word| crp::read_crp(&word, 0));
 
-    debug!("CPU Familly: {}", processor_info.cpu_family);
-    debug!("Firmware file: {}", input);
-    debug!("Dry run: {}", dry_run);
+    match crp_level {
+        Some(level) => info!("CRP: {}", level.name()),
+        None => debug!("CRP: not present in the image"),
+    }
 
-    if processor_info.words_count.is_some() {
-        let result = OpenOptions::new().read(true).write(true).open(input);
-        if let Ok(mut firmware_file) = result {
-            let checksum = processor_info.compute_checksum(&mut firmware_file)?;
-            info!("Checksum: 0x{:x}", checksum);
+    Ok(crp_level)
+}
+
+/// Print the ARM7 exception vectors, then validate the vector table against the memory map of
+/// the part, refusing invalid vector tables unless forced.
+fn check_vectors(
+    firmware: &dyn Image,
+    family: Family,
+    part: Option<&Part>,
+    header: &[u8],
+    force: bool,
+    warnings: &mut Vec<String>,
+) -> Result<(), Error> {
+    // The literal pool of the ARM7 vectors is needed to resolve their targets.
+    let vector_area = firmware
+        .read_vec(0, vectors::ARM_VECTOR_AREA_SIZE)
+        .unwrap_or_else(|_| header.to_vec());
 
-            if !dry_run {
-                firmware_file.seek(SeekFrom::Start(
-                    (processor_info.resulting_word_position * std::mem::size_of::<u32>()) as u64,
-                ))?;
-                firmware_file.write_all(&checksum.to_le_bytes())?;
+    if family == Family::Lpc2000 {
+        for vector in vectors::arm_vectors(&vector_area) {
+            match (vector.instruction, vector.target(&vector_area)) {
+                (ArmInstruction::LoadPc(_), Some(target)) => info!(
+                    "{:<14} 0x{:02x}: {} -> 0x{:08x}",
+                    vector.name, vector.address, vector.instruction, target
+                ),
+                _ => info!(
+                    "{:<14} 0x{:02x}: {}",
+                    vector.name, vector.address, vector.instruction
+                ),
             }
+        }
+    }
+
+    let part = match part {
+        Some(part) => part,
+        None => {
+            debug!("Vector table not validated without a part number");
+
+            return Ok(());
+        }
+    };
+    let mut vector_error = None;
+
+    vectors::validate_vectors(part, &vector_area, |issue| {
+        if issue.is_error() && !force {
+            vector_error = vector_error.or(Some(issue));
         } else {
-            error!("Cannot open file {}: {:?}", input, result);
+            record_warning(warnings, issue.to_string());
+        }
+    });
+
+    match vector_error {
+        Some(issue) => Err(Error::InvalidVectorTable(issue)),
+        None => Ok(()),
+    }
+}
+
+/// Write the patched vector table and headers to the image, sign and encrypt it, then write it
+/// to the output file and to the SB2.1 file.
+fn write_image(
+    firmware: &mut Box<dyn Image>,
+    header: &[u8],
+    boot_block_offset: Option<usize>,
+    report: &mut Report,
+    options: &Options,
+    #[cfg(feature = "secure")] signer: Option<&(RsaPrivateKey, CertBlock)>,
+) -> Result<(), Error> {
+    firmware.write(0, header)?;
+
+    if let (Some(offset), Some(boot_block)) = (boot_block_offset, &report.boot_block) {
+        firmware.write(offset, &boot_block.to_bytes())?;
+    }
+
+    if let Some(image_header) = &report.image_header {
+        let mut data = firmware.read_vec(0, lpc55::HEADER_SIZE)?;
+        image_header.write(&mut data)?;
+        firmware.write(0, &data)?;
+    }
+
+    #[cfg(feature = "secure")]
+    if let Some((key, cert_block)) = signer {
+        let mut data = firmware.read_vec(0, firmware.size())?;
+        let signature = cert_block.sign(&data, key)?;
+
+        data.extend_from_slice(&signature);
+        *firmware = Box::new(BinaryImage { data });
+    }
+
+    // The flash controller encrypts the data programmed by the SB2.1 file.
+    #[cfg(feature = "secure")]
+    if let (Some(path), Some(kek)) = (options.sb_file, options.sb_kek) {
+        let data = sb2::image_file(
+            firmware.as_ref(),
+            &sb2::Keys::generate(kek)?,
+            options.sb_jump,
+            signer.map(|(key, cert_block)| (key, cert_block)),
+        )?;
+        info!(
+            "SB2.1 file: {} bytes{}",
+            data.len(),
+            if signer.is_some() { ", signed" } else { "" }
+        );
+
+        fs::write(path, data).inspect_err(|_| error!("Cannot write file {}", path))?;
+    }
+
+    #[cfg(feature = "secure")]
+    if let Some(regions) = options
+        .prince_regions
+        .as_ref()
+        .filter(|_| !options.prince_decrypt)
+    {
+        let (image, size) = prince::apply_image(firmware.as_ref(), regions)?;
+        info!("PRINCE: {} bytes in encrypted regions", size);
+
+        if size == 0 {
+            record_warning(
+                &mut report.warnings,
+                "Image outside the enabled PRINCE subregions".to_string(),
+            );
+        }
+
+        *firmware = Box::new(image);
+    }
+
+    #[cfg(feature = "secure")]
+    if let (Some(key), Some("prepend")) = (&options.aes_key, options.boot_header_mode) {
+        let mut data = firmware.read_vec(0, firmware.size())?;
+        let boot_header = boot_header::encrypt(&mut data, key)?;
+        info!(
+            "Boot header: AES encrypted, hash value 0x{:016x}",
+            boot_header.hash_value
+        );
+
+        report.boot_header = Some(boot_header);
+        *firmware = Box::new(BinaryImage { data });
+    }
+
+    // Decrypted secure images are written without their header.
+    let boot_header = report
+        .boot_header
+        .filter(|_| options.boot_header_mode != Some("decrypt"));
+
+    write_output(options.output, boot_header.as_ref(), firmware.as_ref())
+        .inspect_err(|_| error!("Cannot write file {}", options.output))?;
+
+    Ok(())
+}
+
+/// Report the result of the verification of the checksum, then of the CRC, signature and SB2.1
+/// file.
+fn check_verification(
+    report: &Report,
+    options: &Options,
+    #[cfg(feature = "secure")] firmware: &dyn Image,
+    crc_mismatch: Option<Error>,
+    signature_error: Option<Error>,
+) -> Result<(), Error> {
+    if report.old_word != report.new_word {
+        return Err(Error::ChecksumMismatch {
+            expected: report.new_word,
+            found: report.old_word,
+        });
+    }
+
+    if !options.json {
+        println!("Checksum match: 0x{:08x}", report.new_word);
+    }
+
+    if let Some(flash_signature) = report.flash_signature.as_ref().filter(|_| !options.json) {
+        println!("Flash signature: {}", format_signature(flash_signature));
+    }
+
+    if let Some(error) = crc_mismatch {
+        return Err(error);
+    }
+
+    if let Some(error) = signature_error {
+        return Err(error);
+    }
+
+    if let Some(hash) = report
+        .root_key_table_hash
+        .as_ref()
+        .filter(|_| !options.json)
+    {
+        println!("Signature valid, root key table hash: {}", hash);
+    }
+
+    #[cfg(feature = "secure")]
+    if let (Some(path), Some(kek)) = (options.sb_file, options.sb_kek) {
+        verify_secure_binary(path, firmware, kek, options.root_key_hash)?;
+
+        if !options.json {
+            println!("SB2.1 file valid: {}", path);
         }
+    }
+
+    Ok(())
+}
+
+/// Compute and insert the checksum of an image, or verify it, handling the headers of the
+/// familly.
+fn run_image(matches: &ArgMatches) -> Result<(), Error> {
+    let processor = matches.value_of("processor").unwrap();
+    #[cfg(feature = "secure")]
+    let mut signer = load_signer(matches)?;
+    let options = Options::parse(matches)?;
+
+    let part = part::get_part_by_name(processor);
+    let family = part
+        .map(|part| part.family)
+        .or_else(|| Family::from_name(processor))
+        .or_else(|| Family::from_part_number(processor))
+        .ok_or_else(|| {
+            error!("Cannot find processor \"{}\"", processor);
+
+            Error::UnknownProcessor
+        })?;
+    let processor_info = family.checksum_info();
+    let signature_width = options.check(family, part)?;
+    let mut warnings = Vec::new();
+
+    if part.is_none() && Family::from_deprecated_name(processor).is_some() {
+        record_warning(
+            &mut warnings,
+            format!(
+                "Processor {} is deprecated, use a part number or the {} familly",
+                processor,
+                family.name()
+            ),
+        );
+    } else if part.is_none() && Family::from_name(processor).is_none() {
+        record_warning(
+            &mut warnings,
+            format!(
+                "Unknown part {}, assuming the {} familly",
+                processor,
+                family.name()
+            ),
+        );
+    }
+
+    debug!("Part: {}", part.map_or("unknown", |part| part.name));
+    debug!("CPU Familly: {}", family.name());
+    debug!("Firmware file: {}", options.input);
+    debug!("Output file: {}", options.output);
+    debug!("Dry run: {}", options.dry_run);
+    debug!("Verify: {}", options.verify);
+
+    let header_size = processor_info.header_size()?;
+    let mut data =
+        read_input(options.input).inspect_err(|_| error!("Cannot open file {}", options.input))?;
+    let boot_header = read_boot_header(&mut data, &options, &mut warnings)?;
+
+    let mut firmware = image::load(data)?;
+    debug!("Image format: {}", firmware.format_name());
+
+    // The vector table is at the flash base address, other segments being loaded elsewhere.
+    let flash_bases = family
+        .flash_bases(part.map_or_else(|| family.flash_base(), Part::flash_base))
+        .collect::<Vec<_>>();
+
+    if !flash_bases.is_empty() {
+        firmware.set_flash_base(&flash_bases)?;
+    }
+
+    debug!("Image base address: 0x{:x}", firmware.base_address());
+
+    if boot_header.is_some() && firmware.format_name() != "binary" {
+        return Err(Error::InvalidImage(
+            "Boot image header only supported for binary images".to_string(),
+        ));
+    }
+
+    let mut boot_block = None;
+
+    if family == Family::Lpc5400 {
+        let link_address = options
+            .load_address
+            .unwrap_or_else(|| firmware.base_address());
+
+        if let Some(image_type) = options.boot_block_type {
+            firmware = Box::new(lpc54::enhance_image(
+                firmware.as_ref(),
+                link_address,
+                image_type,
+            )?);
+        }
+
+        boot_block = lpc54::find_boot_block(firmware.as_ref(), part, link_address, |warning| {
+            record_warning(&mut warnings, warning)
+        })?;
+
+        if let Some((_, boot_block)) = &boot_block {
+            info!(
+                "Boot block: {} image, {} bytes, load address 0x{:08x}, version {}",
+                if boot_block.is_xip() { "XIP" } else { "RAM" },
+                boot_block.image_length,
+                boot_block.load_address,
+                boot_block.version
+            );
+
+            lpc54::check_boot_block(
+                boot_block,
+                firmware.as_ref(),
+                part,
+                link_address,
+                |warning| record_warning(&mut warnings, warning),
+            )?;
+        }
+    }
+
+    #[cfg(feature = "secure")]
+    if let Some(regions) = options
+        .prince_regions
+        .as_ref()
+        .filter(|_| options.prince_decrypt)
+    {
+        let (image, size) = prince::apply_image(firmware.as_ref(), regions)?;
+        info!("PRINCE: {} bytes in encrypted regions", size);
+
+        if size == 0 {
+            record_warning(
+                &mut warnings,
+                "Image outside the enabled PRINCE subregions".to_string(),
+            );
+        }
+
+        firmware = Box::new(image);
+    }
+
+    let mut image_header = None;
+    #[cfg(feature = "secure")]
+    let mut inspection = None;
+
+    if family == Family::Lpc5500 {
+        #[cfg(feature = "secure")]
+        if let Some((_, cert_block)) = signer.as_mut() {
+            let (image, offset) =
+                signed::append_image_cert_block(firmware.as_ref(), cert_block, |warning| {
+                    record_warning(&mut warnings, warning)
+                })?;
+            info!(
+                "Certificate block: {} certificates at 0x{:x}, {} bytes",
+                cert_block.header.certificate_count,
+                offset,
+                cert_block.size()
+            );
+
+            firmware = Box::new(image);
+        }
+
+        let header = lpc55::process_image_header(
+            firmware.as_mut(),
+            options.image_type,
+            options.load_address,
+        )?;
+        info!(
+            "Image header: {} image, {} bytes, load address 0x{:08x}",
+            header.image_type().map_or("unknown", ImageType::name),
+            header.image_length,
+            header.load_address
+        );
+
+        lpc55::check_image_header(&header, firmware.as_ref(), part, |warning| {
+            record_warning(&mut warnings, warning)
+        })?;
+        image_header = Some(header);
+
+        #[cfg(feature = "secure")]
+        if options.inspect
+            && image_header
+                .and_then(|image_header| image_header.image_type())
+                .is_some_and(ImageType::is_signed)
+        {
+            inspection = Some(signed::inspect(&firmware.read_vec(0, firmware.size())?)?);
+        }
+    }
+
+    let crp_level = process_crp(firmware.as_mut(), family, part, options.set_crp)?;
+    let mut header = firmware.read_vec(0, header_size)?;
+    let old_word = processor_info.read_checksum(&header)?;
+
+    check_vectors(
+        firmware.as_ref(),
+        family,
+        part,
+        &header,
+        options.force,
+        &mut warnings,
+    )?;
+
+    // Decrypted images are written as loaded by the BootROM, their checksum being only checked.
+    let checksum = if options.boot_header_mode == Some("decrypt") {
+        let expected = processor_info.compute_checksum(&header)?;
+
+        if expected != old_word {
+            record_warning(
+                &mut warnings,
+                format!(
+                    "Decrypted image checksum mismatch: expected 0x{:08x}, found 0x{:08x}",
+                    expected, old_word
+                ),
+            );
+        }
+
+        old_word
     } else {
-        error!("Checksum not supported for {}", processor_info.cpu_family);
+        processor_info.insert_checksum(&mut header)?
+    };
+    info!("Checksum: 0x{:x}", checksum);
+
+    let mut crc_mismatch = None;
+
+    if let Some(image_header) = image_header
+        .as_mut()
+        .filter(|image_header| image_header.image_type().is_some_and(ImageType::is_crc))
+    {
+        let (crc, mismatch) =
+            lpc55::update_crc(firmware.as_ref(), &header, image_header, options.verify)?;
+        info!("CRC: 0x{:x}", crc);
+
+        crc_mismatch = mismatch;
+    }
+
+    if let Some((offset, boot_block)) = boot_block.as_mut() {
+        let (crc, mismatch) = lpc54::update_crc(
+            firmware.as_ref(),
+            &header,
+            *offset,
+            boot_block,
+            options.verify,
+        )?;
+        info!("CRC: 0x{:x}", crc);
+
+        crc_mismatch = mismatch;
+    }
+
+    let flash_signature = match signature_width.filter(|_| options.flash_signature) {
+        Some(width) => {
+            let flash_signature = signature::compute_flash_signature(
+                firmware.as_ref(),
+                Some(header.as_slice()).filter(|_| !options.verify),
+                width,
+                options.signature_range,
+                |warning| record_warning(&mut warnings, warning),
+            )?;
+            info!(
+                "Flash signature 0x{:08x}-0x{:08x}: {}",
+                flash_signature.start_address,
+                flash_signature.end_address - 1,
+                format_signature(&flash_signature)
+            );
+
+            Some(flash_signature)
+        }
+        None => None,
+    };
+
+    let signed_image = image_header
+        .as_ref()
+        .is_some_and(|image_header| image_header.image_type().is_some_and(ImageType::is_signed));
+
+    #[cfg(feature = "secure")]
+    let (root_key_table_hash, signature_error) = match &signer {
+        Some((_, cert_block)) => (Some(cert_block.root_key_table_hash()), None),
+        None if signed_image && options.verify => {
+            let (hash, error) =
+                signed::verify_image_signature(firmware.as_ref(), options.root_key_hash)?;
+
+            (Some(hash), error)
+        }
+        None => (None, None),
+    };
+    #[cfg(feature = "secure")]
+    let root_key_table_hash = root_key_table_hash
+        .map(|hash| signed::to_hex(&hash))
+        .or_else(|| {
+            inspection
+                .as_ref()
+                .map(|inspection| inspection.root_key_table_hash.clone())
+        });
+    #[cfg(not(feature = "secure"))]
+    let (root_key_table_hash, signature_error): (Option<String>, Option<Error>) = (None, None);
+
+    if let Some(hash) = &root_key_table_hash {
+        info!("Root key table hash: {}", hash);
+    }
+
+    if signed_image && !options.signing && !options.verify && !options.dry_run {
+        record_warning(
+            &mut warnings,
+            "Signed image, patching it invalidates the signature".to_string(),
+        );
+    }
+
+    if let Some(level) = crp_level.filter(|level| level.is_protected()) {
+        record_warning(
+            &mut warnings,
+            format!(
+                "Image enables {}, restricting the access to the part",
+                level.name()
+            ),
+        );
+    }
+
+    let mut report = Report {
+        family: family.name(),
+        part: part.map(|part| part.name),
+        format: firmware.format_name(),
+        base_address: firmware.base_address(),
+        words_summed: processor_info.words_count.unwrap_or(0),
+        checksum_position: processor_info.resulting_word_position,
+        old_word,
+        new_word: checksum,
+        crp: crp_level.map(CrpLevel::name),
+        flash_signature,
+        boot_header,
+        boot_block: boot_block.map(|(_, boot_block)| boot_block),
+        image_header,
+        root_key_table_hash,
+        #[cfg(feature = "secure")]
+        inspection,
+        modified: !options.verify && !options.dry_run,
+        warnings,
+    };
+
+    if report.modified && crp_level == Some(CrpLevel::Crp3) && !options.allow_crp3 {
+        report.modified = false;
+
+        if options.json {
+            println!("{}", serde_json::to_string_pretty(&report).unwrap());
+        }
+
+        return Err(Error::PermanentCrp);
+    }
+
+    if report.modified {
+        write_image(
+            &mut firmware,
+            &header,
+            boot_block.map(|(offset, _)| offset),
+            &mut report,
+            &options,
+            #[cfg(feature = "secure")]
+            signer.as_ref(),
+        )?;
+    }
+
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(&report).unwrap());
+    }
+
+    #[cfg(feature = "secure")]
+    if options.inspect && !options.json {
+        print_inspection(&report, options.root_key_hash);
+    }
+
+    if options.verify {
+        check_verification(
+            &report,
+            &options,
+            #[cfg(feature = "secure")]
+            firmware.as_ref(),
+            crc_mismatch,
+            signature_error,
+        )?;
     }
 
     Ok(())
 }
+
+fn run(matches: &ArgMatches) -> Result<(), Error> {
+    if matches.is_present("list") {
+        list_parts();
+
+        return Ok(());
+    }
+
+    #[cfg(feature = "secure")]
+    if let Some(page) = matches.value_of("protected-page") {
+        return run_protected_page(matches, page);
+    }
+
+    run_image(matches)
+}
@@ -0,0 +1,940 @@
+//! Database of the LPC processors.
+//!
+//! Parts are keyed by their full part number, ordering code suffixes (package, revision) being
+//! ignored during lookup.
+
+use crate::signature::SignatureWidth;
+use crate::{ProcessorChecksumInfo, PROCESSOR_CHECKSUM};
+use Core::*;
+use Family::*;
+
+/// Size of a kilobyte in bytes.
+const KB: u32 = 1024;
+
+/// Offset of the TrustZone secure aliases of the LPC5500 flash and RAM.
+const SECURE_ALIAS_OFFSET: u32 = 0x1000_0000;
+
+/// CPU core of a processor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Core {
+    /// ARM7TDMI-S.
+    Arm7Tdmi,
+    /// ARM926EJ-S.
+    Arm926Ejs,
+    /// ARM968E-S.
+    Arm968Es,
+    /// Cortex-M0.
+    CortexM0,
+    /// Cortex-M0+.
+    CortexM0Plus,
+    /// Cortex-M3.
+    CortexM3,
+    /// Cortex-M4.
+    CortexM4,
+    /// Cortex-M33.
+    CortexM33,
+}
+
+impl Core {
+    /// The name of the core.
+    pub fn name(self) -> &'static str {
+        match self {
+            Core::Arm7Tdmi => "ARM7TDMI-S",
+            Core::Arm926Ejs => "ARM926EJ-S",
+            Core::Arm968Es => "ARM968E-S",
+            Core::CortexM0 => "Cortex-M0",
+            Core::CortexM0Plus => "Cortex-M0+",
+            Core::CortexM3 => "Cortex-M3",
+            Core::CortexM4 => "Cortex-M4",
+            Core::CortexM33 => "Cortex-M33",
+        }
+    }
+
+    /// Returns true if the core is a Cortex-M, using a vector table of addresses.
+    pub fn is_cortex_m(self) -> bool {
+        match self {
+            Core::Arm7Tdmi | Core::Arm926Ejs | Core::Arm968Es => false,
+            Core::CortexM0
+            | Core::CortexM0Plus
+            | Core::CortexM3
+            | Core::CortexM4
+            | Core::CortexM33 => true,
+        }
+    }
+}
+
+/// Processor familly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Family {
+    /// LPC800 (Cortex-M0+).
+    Lpc800,
+    /// LPC1100 (Cortex-M0).
+    Lpc1100,
+    /// LPC1200 (Cortex-M0).
+    Lpc1200,
+    /// LPC1300 (Cortex-M3).
+    Lpc1300,
+    /// LPC1500 (Cortex-M3).
+    Lpc1500,
+    /// LPC1700 (Cortex-M3).
+    Lpc1700,
+    /// LPC1800 (Cortex-M3).
+    Lpc1800,
+    /// LPC2000 (ARM7).
+    Lpc2000,
+    /// LPC2900 (ARM9).
+    Lpc2900,
+    /// LPC3000 (ARM9).
+    Lpc3000,
+    /// LPC4000 (Cortex-M4).
+    Lpc4000,
+    /// LPC4300 (Cortex-M4).
+    Lpc4300,
+    /// LPC5400 (Cortex-M4).
+    Lpc5400,
+    /// LPC5500 (Cortex-M33).
+    Lpc5500,
+}
+
+impl Family {
+    /// All the famillies.
+    pub const ALL: &'static [Family] = &[
+        Family::Lpc800,
+        Family::Lpc1100,
+        Family::Lpc1200,
+        Family::Lpc1300,
+        Family::Lpc1500,
+        Family::Lpc1700,
+        Family::Lpc1800,
+        Family::Lpc2000,
+        Family::Lpc2900,
+        Family::Lpc3000,
+        Family::Lpc4000,
+        Family::Lpc4300,
+        Family::Lpc5400,
+        Family::Lpc5500,
+    ];
+
+    /// The name of the familly.
+    pub fn name(self) -> &'static str {
+        match self {
+            Family::Lpc800 => "LPC800",
+            Family::Lpc1100 => "LPC1100",
+            Family::Lpc1200 => "LPC1200",
+            Family::Lpc1300 => "LPC1300",
+            Family::Lpc1500 => "LPC1500",
+            Family::Lpc1700 => "LPC1700",
+            Family::Lpc1800 => "LPC1800",
+            Family::Lpc2000 => "LPC2000",
+            Family::Lpc2900 => "LPC2900",
+            Family::Lpc3000 => "LPC3000",
+            Family::Lpc4000 => "LPC4000",
+            Family::Lpc4300 => "LPC4300",
+            Family::Lpc5400 => "LPC5400",
+            Family::Lpc5500 => "LPC5500",
+        }
+    }
+
+    /// Get a familly by its name (e.g. LPC1700), or by a deprecated name.
+    pub fn from_name(name: &str) -> Option<Family> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|family| family.name().eq_ignore_ascii_case(name.trim()))
+            .or_else(|| Self::from_deprecated_name(name))
+    }
+
+    /// Get a familly by a deprecated name.
+    ///
+    /// LPC1000 was the default processor before parts and famillies were known, standing for the
+    /// LPC1xxx Cortex-M parts sharing the LPC1700 checksum layout.
+    pub fn from_deprecated_name(name: &str) -> Option<Family> {
+        Some(Family::Lpc1700).filter(|_| name.trim().eq_ignore_ascii_case("LPC1000"))
+    }
+
+    /// Get the familly of a part number missing from the database, from its prefix (e.g. LPC2000
+    /// for LPC2129).
+    pub fn from_part_number(part_number: &str) -> Option<Family> {
+        const PREFIXES: &[(&str, Family)] = &[
+            ("LPC8", Family::Lpc800),
+            ("LPC11", Family::Lpc1100),
+            ("LPC12", Family::Lpc1200),
+            ("LPC13", Family::Lpc1300),
+            ("LPC15", Family::Lpc1500),
+            ("LPC17", Family::Lpc1700),
+            ("LPC18", Family::Lpc1800),
+            ("LPC29", Family::Lpc2900),
+            ("LPC2", Family::Lpc2000),
+            ("LPC3", Family::Lpc3000),
+            ("LPC40", Family::Lpc4000),
+            ("LPC43", Family::Lpc4300),
+            ("LPC51", Family::Lpc5400),
+            ("LPC54", Family::Lpc5400),
+            ("LPC55", Family::Lpc5500),
+        ];
+        let part_number = part_number.trim().as_bytes();
+
+        PREFIXES
+            .iter()
+            .find(|(prefix, _)| {
+                part_number.len() > prefix.len()
+                    && part_number[..prefix.len()].eq_ignore_ascii_case(prefix.as_bytes())
+            })
+            .map(|(_, family)| *family)
+    }
+
+    /// Get the width of the flash signature generator of the familly, if any.
+    pub fn flash_signature(self) -> Option<SignatureWidth> {
+        match self {
+            Family::Lpc800 => Some(SignatureWidth::Bits32),
+            Family::Lpc1100
+            | Family::Lpc1300
+            | Family::Lpc1500
+            | Family::Lpc1700
+            | Family::Lpc4000 => Some(SignatureWidth::Bits128),
+            _ => None,
+        }
+    }
+
+    /// Get the flash base address shared by the parts of the familly.
+    ///
+    /// Returns `None` for the famillies with flashless parts, booting from external memories.
+    pub fn flash_base(self) -> Option<u32> {
+        match self {
+            Family::Lpc800
+            | Family::Lpc1100
+            | Family::Lpc1200
+            | Family::Lpc1300
+            | Family::Lpc1500
+            | Family::Lpc1700
+            | Family::Lpc2000
+            | Family::Lpc4000
+            | Family::Lpc5500 => Some(0),
+            Family::Lpc2900 => Some(0x2000_0000),
+            Family::Lpc1800 | Family::Lpc3000 | Family::Lpc4300 | Family::Lpc5400 => None,
+        }
+    }
+
+    /// Get the given flash base address followed by its TrustZone secure alias, if any.
+    pub fn flash_bases(self, flash_base: Option<u32>) -> impl Iterator<Item = u32> {
+        let secure_alias = flash_base
+            .filter(|_| self == Family::Lpc5500)
+            .map(|flash_base| flash_base + SECURE_ALIAS_OFFSET);
+
+        flash_base.into_iter().chain(secure_alias)
+    }
+
+    /// Get the checksum information of the familly.
+    pub fn checksum_info(self) -> &'static ProcessorChecksumInfo {
+        let cpu_family = match self {
+            Family::Lpc800 => "LPC8",
+            Family::Lpc1100
+            | Family::Lpc1200
+            | Family::Lpc1300
+            | Family::Lpc1500
+            | Family::Lpc1700
+            | Family::Lpc1800 => "LPC1",
+            Family::Lpc2000 => "LPC2",
+            Family::Lpc2900 => "LPC29",
+            Family::Lpc3000 => "LPC3",
+            Family::Lpc4000 | Family::Lpc4300 => "LPC4",
+            Family::Lpc5400 | Family::Lpc5500 => "LPC5",
+        };
+
+        PROCESSOR_CHECKSUM
+            .iter()
+            .find(|processor| processor.cpu_family == cpu_family)
+            .unwrap()
+    }
+}
+
+/// A memory region.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryRegion {
+    /// The address of the region.
+    pub base: u32,
+    /// The size in bytes of the region.
+    pub size: u32,
+    /// Whether the region is the TrustZone secure alias of another region of the part.
+    pub secure_alias: bool,
+}
+
+impl MemoryRegion {
+    /// Returns true if the given address is inside the region.
+    pub fn contains(&self, address: u32) -> bool {
+        address >= self.base && u64::from(address) < u64::from(self.base) + u64::from(self.size)
+    }
+}
+
+/// Consecutive flash sectors of identical size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SectorGroup {
+    /// The count of sectors.
+    pub count: u32,
+    /// The size in bytes of a sector.
+    pub size: u32,
+}
+
+/// Features of the boot ROM.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BootRom {
+    /// In-System Programming over UART.
+    pub uart_isp: bool,
+    /// In-System Programming over USB.
+    pub usb_isp: bool,
+    /// In-System Programming over CAN.
+    pub can_isp: bool,
+    /// In-Application Programming API.
+    pub iap: bool,
+    /// Code Read Protection word.
+    pub crp: bool,
+    /// Flash signature generator.
+    pub flash_signature: bool,
+}
+
+/// Description of a LPC processor.
+#[derive(Debug)]
+pub struct Part {
+    /// The part number.
+    pub name: &'static str,
+    /// The familly of the part.
+    pub family: Family,
+    /// The CPU core of the part.
+    pub core: Core,
+    /// The internal flash banks, empty on flashless parts.
+    pub flash: &'static [MemoryRegion],
+    /// The sector layout of a flash bank.
+    pub sectors: &'static [SectorGroup],
+    /// The internal RAM regions.
+    pub ram: &'static [MemoryRegion],
+    /// The features of the boot ROM.
+    pub boot_rom: BootRom,
+}
+
+impl Part {
+    /// The address of the first flash bank, where the image starts.
+    pub fn flash_base(&self) -> Option<u32> {
+        self.flash.first().map(|bank| bank.base)
+    }
+
+    /// The total size in bytes of the internal flash.
+    pub fn flash_size(&self) -> u32 {
+        self.flash
+            .iter()
+            .filter(|bank| !bank.secure_alias)
+            .map(|bank| bank.size)
+            .sum()
+    }
+
+    /// The total size in bytes of the internal RAM.
+    pub fn ram_size(&self) -> u32 {
+        self.ram
+            .iter()
+            .filter(|region| !region.secure_alias)
+            .map(|region| region.size)
+            .sum()
+    }
+
+    /// Get the checksum information of the part.
+    pub fn checksum_info(&self) -> &'static ProcessorChecksumInfo {
+        self.family.checksum_info()
+    }
+
+    /// Returns true if the given part number designates this part.
+    ///
+    /// Ordering code suffixes are accepted (e.g. LPC1768FBD100 for LPC1768).
+    fn matches(&self, part_number: &str) -> bool {
+        let part_number = part_number.trim().as_bytes();
+        let name = self.name.as_bytes();
+
+        part_number.len() >= name.len()
+            && part_number[..name.len()].eq_ignore_ascii_case(name)
+            && part_number
+                .get(name.len())
+                .is_none_or(|value| !value.is_ascii_digit())
+    }
+}
+
+const fn region(base: u32, size: u32) -> MemoryRegion {
+    MemoryRegion {
+        base,
+        size,
+        secure_alias: false,
+    }
+}
+
+const fn sectors(count: u32, size: u32) -> SectorGroup {
+    SectorGroup { count, size }
+}
+
+/// The TrustZone secure alias of a region.
+const fn secure(region: MemoryRegion) -> MemoryRegion {
+    MemoryRegion {
+        base: region.base + SECURE_ALIAS_OFFSET,
+        secure_alias: true,
+        ..region
+    }
+}
+
+/// Boot ROM of the LPC800, LPC1100, LPC1200, LPC1300, LPC1700 and LPC4000 famillies.
+const CORTEX_M_BOOT_ROM: BootRom = BootRom {
+    uart_isp: true,
+    usb_isp: false,
+    can_isp: false,
+    iap: true,
+    crp: true,
+    flash_signature: true,
+};
+
+/// Boot ROM with USB ISP support.
+const CORTEX_M_USB_BOOT_ROM: BootRom = BootRom {
+    usb_isp: true,
+    ..CORTEX_M_BOOT_ROM
+};
+
+/// Boot ROM with CAN ISP support.
+const CORTEX_M_CAN_BOOT_ROM: BootRom = BootRom {
+    can_isp: true,
+    ..CORTEX_M_BOOT_ROM
+};
+
+/// Boot ROM of the flashless LPC1800 and LPC4300 parts.
+const FLASHLESS_BOOT_ROM: BootRom = BootRom {
+    uart_isp: true,
+    usb_isp: true,
+    can_isp: false,
+    iap: false,
+    crp: false,
+    flash_signature: false,
+};
+
+/// Boot ROM of the LPC2000 familly.
+const ARM7_BOOT_ROM: BootRom = BootRom {
+    uart_isp: true,
+    usb_isp: false,
+    can_isp: false,
+    iap: true,
+    crp: true,
+    flash_signature: false,
+};
+
+/// Boot ROM of the ARM9 famillies, without any checksum or ISP support.
+const ARM9_BOOT_ROM: BootRom = BootRom {
+    uart_isp: false,
+    usb_isp: false,
+    can_isp: false,
+    iap: false,
+    crp: false,
+    flash_signature: false,
+};
+
+/// Boot ROM of the LPC5400 and LPC5500 famillies, protection being handled outside the image.
+const LPC5_BOOT_ROM: BootRom = BootRom {
+    uart_isp: true,
+    usb_isp: true,
+    can_isp: false,
+    iap: true,
+    crp: false,
+    flash_signature: false,
+};
+
+const FLASH_32K: &[MemoryRegion] = &[region(0, 32 * KB)];
+const FLASH_64K: &[MemoryRegion] = &[region(0, 64 * KB)];
+const FLASH_128K: &[MemoryRegion] = &[region(0, 128 * KB)];
+const FLASH_256K: &[MemoryRegion] = &[region(0, 256 * KB)];
+const FLASH_512K: &[MemoryRegion] = &[region(0, 512 * KB)];
+
+/// Flash layout of the LPC1800 and LPC4300 parts with two 512 KB banks.
+const FLASH_2X512K: &[MemoryRegion] =
+    &[region(0x1A00_0000, 512 * KB), region(0x1B00_0000, 512 * KB)];
+
+/// Flash layout of the LPC1800 and LPC4300 parts with two 256 KB banks.
+const FLASH_2X256K: &[MemoryRegion] =
+    &[region(0x1A00_0000, 256 * KB), region(0x1B00_0000, 256 * KB)];
+
+const SECTORS_4K_X8: &[SectorGroup] = &[sectors(8, 4 * KB)];
+const SECTORS_4K_X16: &[SectorGroup] = &[sectors(16, 4 * KB)];
+
+/// Sector layout of the LPC1700 and LPC4000 famillies.
+const LPC17_SECTORS_128K: &[SectorGroup] = &[sectors(16, 4 * KB), sectors(2, 32 * KB)];
+const LPC17_SECTORS_256K: &[SectorGroup] = &[sectors(16, 4 * KB), sectors(6, 32 * KB)];
+const LPC17_SECTORS_512K: &[SectorGroup] = &[sectors(16, 4 * KB), sectors(14, 32 * KB)];
+
+/// Sector layout of the LPC1800 and LPC4300 flash banks.
+const LPC18_SECTORS_256K: &[SectorGroup] = &[sectors(8, 8 * KB), sectors(3, 64 * KB)];
+const LPC18_SECTORS_512K: &[SectorGroup] = &[sectors(8, 8 * KB), sectors(7, 64 * KB)];
+
+/// Sector layout of the LPC2100 parts with 8 KB sectors.
+const LPC210X_SECTORS_128K: &[SectorGroup] = &[sectors(16, 8 * KB)];
+
+/// Sector layout of the LPC213x, LPC214x, LPC23xx and LPC24xx parts.
+const LPC21_SECTORS_64K: &[SectorGroup] = &[sectors(8, 4 * KB), sectors(1, 32 * KB)];
+const LPC21_SECTORS_128K: &[SectorGroup] = &[sectors(8, 4 * KB), sectors(3, 32 * KB)];
+const LPC21_SECTORS_256K: &[SectorGroup] = &[sectors(8, 4 * KB), sectors(7, 32 * KB)];
+const LPC21_SECTORS_512K: &[SectorGroup] =
+    &[sectors(8, 4 * KB), sectors(14, 32 * KB), sectors(6, 4 * KB)];
+
+/// Sector layout of the LPC2200 parts.
+const LPC22_SECTORS_256K: &[SectorGroup] =
+    &[sectors(8, 8 * KB), sectors(2, 64 * KB), sectors(8, 8 * KB)];
+
+/// Sector layout of the LPC2900 parts.
+const LPC29_SECTORS_512K: &[SectorGroup] = &[sectors(8, 8 * KB), sectors(7, 64 * KB)];
+const LPC29_SECTORS_768K: &[SectorGroup] = &[sectors(8, 8 * KB), sectors(11, 64 * KB)];
+
+const SECTORS_32K_X8: &[SectorGroup] = &[sectors(8, 32 * KB)];
+const SECTORS_32K_X16: &[SectorGroup] = &[sectors(16, 32 * KB)];
+const SECTORS_32K_X20: &[SectorGroup] = &[sectors(20, 32 * KB)];
+
+/// RAM layout of the LPC11E36 and LPC11E37 parts, with USB RAM.
+const LPC11E3X_RAM: &[MemoryRegion] = &[
+    region(0x1000_0000, 8 * KB),
+    region(0x2000_0000, 2 * KB),
+    region(0x2000_4000, 2 * KB),
+];
+
+/// RAM layout of the LPC175x and LPC176x parts with AHB SRAM.
+const LPC17_RAM_32K_32K: &[MemoryRegion] = &[
+    region(0x1000_0000, 32 * KB),
+    region(0x2007_C000, 16 * KB),
+    region(0x2008_0000, 16 * KB),
+];
+const LPC17_RAM_16K_16K: &[MemoryRegion] =
+    &[region(0x1000_0000, 16 * KB), region(0x2007_C000, 16 * KB)];
+
+/// RAM layout of the LPC177x, LPC178x and LPC407x/LPC408x parts.
+const LPC178X_RAM: &[MemoryRegion] = &[
+    region(0x1000_0000, 64 * KB),
+    region(0x2000_0000, 16 * KB),
+    region(0x2000_4000, 16 * KB),
+];
+
+/// RAM layout of the LPC1800 and LPC4300 flash parts.
+const LPC18_RAM_136K: &[MemoryRegion] = &[
+    region(0x1000_0000, 32 * KB),
+    region(0x1008_0000, 40 * KB),
+    region(0x2000_0000, 64 * KB),
+];
+const LPC18_RAM_104K: &[MemoryRegion] = &[
+    region(0x1000_0000, 32 * KB),
+    region(0x1008_0000, 40 * KB),
+    region(0x2000_0000, 32 * KB),
+];
+
+/// RAM layout of the LPC1800 and LPC4300 flashless parts.
+const LPC18_RAM_200K: &[MemoryRegion] = &[
+    region(0x1000_0000, 96 * KB),
+    region(0x1008_0000, 40 * KB),
+    region(0x2000_0000, 64 * KB),
+];
+const LPC43_RAM_264K: &[MemoryRegion] = &[
+    region(0x1000_0000, 128 * KB),
+    region(0x1008_0000, 72 * KB),
+    region(0x2000_0000, 64 * KB),
+];
+
+/// RAM layout of the LPC4370 flashless part, with the RAM of the M0 subsystem.
+const LPC4370_RAM: &[MemoryRegion] = &[
+    region(0x1000_0000, 128 * KB),
+    region(0x1008_0000, 72 * KB),
+    region(0x1800_0000, 18 * KB),
+    region(0x2000_0000, 64 * KB),
+];
+
+/// RAM layout of the LPC2300 and LPC2400 parts, with USB and Ethernet RAM.
+const LPC23_RAM_32K: &[MemoryRegion] = &[
+    region(0x4000_0000, 32 * KB),
+    region(0x7FD0_0000, 8 * KB),
+    region(0x7FE0_0000, 16 * KB),
+];
+const LPC23_RAM_64K: &[MemoryRegion] = &[
+    region(0x4000_0000, 64 * KB),
+    region(0x7FD0_0000, 16 * KB),
+    region(0x7FE0_0000, 16 * KB),
+];
+
+/// RAM layout of the LPC2146 and LPC2148 parts, with USB DMA RAM.
+const LPC214X_USB_RAM: &[MemoryRegion] =
+    &[region(0x4000_0000, 32 * KB), region(0x7FD0_0000, 8 * KB)];
+
+/// RAM layout of the LPC2900 familly.
+const LPC29_RAM: &[MemoryRegion] = &[region(0x8000_0000, 32 * KB), region(0x8000_8000, 16 * KB)];
+
+/// RAM layout of the LPC5410x parts.
+const LPC5410X_RAM: &[MemoryRegion] = &[
+    region(0x0200_0000, 64 * KB),
+    region(0x0201_0000, 32 * KB),
+    region(0x0300_0000, 8 * KB),
+];
+
+/// RAM layout of the LPC51U68 part.
+const LPC51U68_RAM: &[MemoryRegion] = &[region(0x0400_0000, 32 * KB), region(0x2000_0000, 64 * KB)];
+
+/// RAM layout of the LPC5411x parts.
+const LPC5411X_RAM: &[MemoryRegion] =
+    &[region(0x0400_0000, 32 * KB), region(0x2000_0000, 160 * KB)];
+
+/// RAM layout of the LPC546xx parts.
+const LPC546XX_RAM: &[MemoryRegion] =
+    &[region(0x0400_0000, 32 * KB), region(0x2000_0000, 168 * KB)];
+
+/// RAM layout of the LPC540xx flashless parts.
+const LPC540XX_RAM: &[MemoryRegion] =
+    &[region(0x0000_0000, 192 * KB), region(0x2000_0000, 168 * KB)];
+
+/// Flash layout of the LPC5500 familly, followed by its secure alias.
+const LPC55_FLASH_64K: &[MemoryRegion] = &[region(0, 64 * KB), secure(region(0, 64 * KB))];
+const LPC55_FLASH_128K: &[MemoryRegion] = &[region(0, 128 * KB), secure(region(0, 128 * KB))];
+const LPC55_FLASH_256K: &[MemoryRegion] = &[region(0, 256 * KB), secure(region(0, 256 * KB))];
+const LPC55_FLASH_512K: &[MemoryRegion] = &[region(0, 512 * KB), secure(region(0, 512 * KB))];
+const LPC55_FLASH_640K: &[MemoryRegion] = &[region(0, 640 * KB), secure(region(0, 640 * KB))];
+
+/// RAM layout of the LPC55S6x parts, followed by its secure alias.
+const LPC55S6X_RAM_320K: &[MemoryRegion] = &[
+    region(0x0400_0000, 32 * KB),
+    region(0x2000_0000, 272 * KB),
+    secure(region(0x0400_0000, 32 * KB)),
+    secure(region(0x2000_0000, 272 * KB)),
+];
+const LPC55S6X_RAM_144K: &[MemoryRegion] = &[
+    region(0x0400_0000, 32 * KB),
+    region(0x2000_0000, 96 * KB),
+    secure(region(0x0400_0000, 32 * KB)),
+    secure(region(0x2000_0000, 96 * KB)),
+];
+
+/// RAM layout of the LPC552x and LPC55S2x parts, followed by its secure alias.
+const LPC552X_RAM_256K: &[MemoryRegion] = &[
+    region(0x0400_0000, 32 * KB),
+    region(0x2000_0000, 208 * KB),
+    secure(region(0x0400_0000, 32 * KB)),
+    secure(region(0x2000_0000, 208 * KB)),
+];
+const LPC552X_RAM_144K: &[MemoryRegion] = &[
+    region(0x0400_0000, 32 * KB),
+    region(0x2000_0000, 96 * KB),
+    secure(region(0x0400_0000, 32 * KB)),
+    secure(region(0x2000_0000, 96 * KB)),
+];
+
+/// RAM layout of the LPC553x and LPC55S3x parts, followed by its secure alias.
+const LPC553X_RAM: &[MemoryRegion] = &[
+    region(0x0400_0000, 16 * KB),
+    region(0x2000_0000, 112 * KB),
+    secure(region(0x0400_0000, 16 * KB)),
+    secure(region(0x2000_0000, 112 * KB)),
+];
+
+/// RAM layout of the LPC551x, LPC55S1x, LPC550x and LPC55S0x parts, followed by its secure alias.
+const LPC551X_RAM: &[MemoryRegion] = &[
+    region(0x0400_0000, 16 * KB),
+    region(0x2000_0000, 64 * KB),
+    secure(region(0x0400_0000, 16 * KB)),
+    secure(region(0x2000_0000, 64 * KB)),
+];
+
+const fn part(
+    name: &'static str,
+    family: Family,
+    core: Core,
+    flash: &'static [MemoryRegion],
+    sectors: &'static [SectorGroup],
+    ram: &'static [MemoryRegion],
+    boot_rom: BootRom,
+) -> Part {
+    Part {
+        name,
+        family,
+        core,
+        flash,
+        sectors,
+        ram,
+        boot_rom,
+    }
+}
+
+/// All the known LPC processors.
+#[rustfmt::skip]
+pub static PARTS: &[Part] = &[
+    // LPC800
+    part("LPC802", Lpc800, CortexM0Plus, &[region(0, 16 * KB)], &[sectors(16, KB)], &[region(0x1000_0000, 2 * KB)], CORTEX_M_BOOT_ROM),
+    part("LPC804", Lpc800, CortexM0Plus, FLASH_32K, &[sectors(32, KB)], &[region(0x1000_0000, 4 * KB)], CORTEX_M_BOOT_ROM),
+    part("LPC810", Lpc800, CortexM0Plus, &[region(0, 4 * KB)], &[sectors(4, KB)], &[region(0x1000_0000, KB)], CORTEX_M_BOOT_ROM),
+    part("LPC811", Lpc800, CortexM0Plus, &[region(0, 8 * KB)], &[sectors(8, KB)], &[region(0x1000_0000, 2 * KB)], CORTEX_M_BOOT_ROM),
+    part("LPC812", Lpc800, CortexM0Plus, &[region(0, 16 * KB)], &[sectors(16, KB)], &[region(0x1000_0000, 4 * KB)], CORTEX_M_BOOT_ROM),
+    part("LPC822", Lpc800, CortexM0Plus, &[region(0, 16 * KB)], &[sectors(16, KB)], &[region(0x1000_0000, 4 * KB)], CORTEX_M_BOOT_ROM),
+    part("LPC824", Lpc800, CortexM0Plus, FLASH_32K, &[sectors(32, KB)], &[region(0x1000_0000, 8 * KB)], CORTEX_M_BOOT_ROM),
+    part("LPC832", Lpc800, CortexM0Plus, &[region(0, 16 * KB)], &[sectors(16, KB)], &[region(0x1000_0000, 4 * KB)], CORTEX_M_BOOT_ROM),
+    part("LPC834", Lpc800, CortexM0Plus, FLASH_32K, &[sectors(32, KB)], &[region(0x1000_0000, 4 * KB)], CORTEX_M_BOOT_ROM),
+    part("LPC844", Lpc800, CortexM0Plus, FLASH_64K, &[sectors(64, KB)], &[region(0x1000_0000, 8 * KB)], CORTEX_M_BOOT_ROM),
+    part("LPC845", Lpc800, CortexM0Plus, FLASH_64K, &[sectors(64, KB)], &[region(0x1000_0000, 16 * KB)], CORTEX_M_BOOT_ROM),
+    part("LPC8N04", Lpc800, CortexM0Plus, FLASH_32K, &[sectors(32, KB)], &[region(0x1000_0000, 8 * KB)], CORTEX_M_BOOT_ROM),
+    // LPC1100
+    part("LPC1110", Lpc1100, CortexM0, &[region(0, 4 * KB)], &[sectors(1, 4 * KB)], &[region(0x1000_0000, KB)], CORTEX_M_BOOT_ROM),
+    part("LPC1111", Lpc1100, CortexM0, &[region(0, 8 * KB)], &[sectors(2, 4 * KB)], &[region(0x1000_0000, 2 * KB)], CORTEX_M_BOOT_ROM),
+    part("LPC1112", Lpc1100, CortexM0, &[region(0, 16 * KB)], &[sectors(4, 4 * KB)], &[region(0x1000_0000, 4 * KB)], CORTEX_M_BOOT_ROM),
+    part("LPC1113", Lpc1100, CortexM0, &[region(0, 24 * KB)], &[sectors(6, 4 * KB)], &[region(0x1000_0000, 8 * KB)], CORTEX_M_BOOT_ROM),
+    part("LPC1114", Lpc1100, CortexM0, FLASH_32K, SECTORS_4K_X8, &[region(0x1000_0000, 8 * KB)], CORTEX_M_BOOT_ROM),
+    part("LPC1115", Lpc1100, CortexM0, FLASH_64K, SECTORS_4K_X16, &[region(0x1000_0000, 8 * KB)], CORTEX_M_BOOT_ROM),
+    part("LPC11C14", Lpc1100, CortexM0, FLASH_32K, SECTORS_4K_X8, &[region(0x1000_0000, 8 * KB)], CORTEX_M_CAN_BOOT_ROM),
+    part("LPC11C24", Lpc1100, CortexM0, FLASH_32K, SECTORS_4K_X8, &[region(0x1000_0000, 8 * KB)], CORTEX_M_CAN_BOOT_ROM),
+    part("LPC11A02", Lpc1100, CortexM0, &[region(0, 16 * KB)], &[sectors(4, 4 * KB)], &[region(0x1000_0000, 4 * KB)], CORTEX_M_BOOT_ROM),
+    part("LPC11A04", Lpc1100, CortexM0, FLASH_32K, SECTORS_4K_X8, &[region(0x1000_0000, 8 * KB)], CORTEX_M_BOOT_ROM),
+    part("LPC11A11", Lpc1100, CortexM0, &[region(0, 8 * KB)], &[sectors(2, 4 * KB)], &[region(0x1000_0000, 2 * KB)], CORTEX_M_BOOT_ROM),
+    part("LPC11A12", Lpc1100, CortexM0, &[region(0, 16 * KB)], &[sectors(4, 4 * KB)], &[region(0x1000_0000, 4 * KB)], CORTEX_M_BOOT_ROM),
+    part("LPC11A13", Lpc1100, CortexM0, &[region(0, 24 * KB)], &[sectors(6, 4 * KB)], &[region(0x1000_0000, 6 * KB)], CORTEX_M_BOOT_ROM),
+    part("LPC11A14", Lpc1100, CortexM0, FLASH_32K, SECTORS_4K_X8, &[region(0x1000_0000, 8 * KB)], CORTEX_M_BOOT_ROM),
+    part("LPC11E11", Lpc1100, CortexM0, &[region(0, 8 * KB)], &[sectors(2, 4 * KB)], &[region(0x1000_0000, 4 * KB)], CORTEX_M_BOOT_ROM),
+    part("LPC11E12", Lpc1100, CortexM0, &[region(0, 16 * KB)], &[sectors(4, 4 * KB)], &[region(0x1000_0000, 6 * KB)], CORTEX_M_BOOT_ROM),
+    part("LPC11E13", Lpc1100, CortexM0, &[region(0, 24 * KB)], &[sectors(6, 4 * KB)], &[region(0x1000_0000, 8 * KB)], CORTEX_M_BOOT_ROM),
+    part("LPC11E14", Lpc1100, CortexM0, FLASH_32K, SECTORS_4K_X8, &[region(0x1000_0000, 8 * KB), region(0x2000_0000, 2 * KB)], CORTEX_M_BOOT_ROM),
+    part("LPC11E36", Lpc1100, CortexM0, &[region(0, 96 * KB)], &[sectors(24, 4 * KB)], LPC11E3X_RAM, CORTEX_M_BOOT_ROM),
+    part("LPC11E37", Lpc1100, CortexM0, FLASH_128K, &[sectors(32, 4 * KB)], LPC11E3X_RAM, CORTEX_M_BOOT_ROM),
+    part("LPC11U14", Lpc1100, CortexM0, FLASH_32K, SECTORS_4K_X8, &[region(0x1000_0000, 4 * KB), region(0x2000_4000, 2 * KB)], CORTEX_M_USB_BOOT_ROM),
+    part("LPC11U24", Lpc1100, CortexM0, FLASH_32K, SECTORS_4K_X8, &[region(0x1000_0000, 6 * KB), region(0x2000_4000, 2 * KB)], CORTEX_M_USB_BOOT_ROM),
+    part("LPC11U35", Lpc1100, CortexM0, FLASH_64K, SECTORS_4K_X16, &[region(0x1000_0000, 8 * KB), region(0x2000_4000, 2 * KB)], CORTEX_M_USB_BOOT_ROM),
+    part("LPC11U37", Lpc1100, CortexM0, FLASH_128K, &[sectors(32, 4 * KB)], &[region(0x1000_0000, 8 * KB), region(0x2000_4000, 2 * KB)], CORTEX_M_USB_BOOT_ROM),
+    part("LPC11U68", Lpc1100, CortexM0Plus, &[region(0, 256 * KB)], &[sectors(24, 4 * KB), sectors(5, 32 * KB)], &[region(0x1000_0000, 32 * KB), region(0x2000_0000, 2 * KB), region(0x2000_4000, 2 * KB)], CORTEX_M_USB_BOOT_ROM),
+    // LPC1200
+    part("LPC1224", Lpc1200, CortexM0, FLASH_32K, SECTORS_4K_X8, &[region(0x1000_0000, 4 * KB)], CORTEX_M_BOOT_ROM),
+    part("LPC1225", Lpc1200, CortexM0, FLASH_64K, SECTORS_4K_X16, &[region(0x1000_0000, 8 * KB)], CORTEX_M_BOOT_ROM),
+    part("LPC1226", Lpc1200, CortexM0, &[region(0, 96 * KB)], &[sectors(24, 4 * KB)], &[region(0x1000_0000, 8 * KB)], CORTEX_M_BOOT_ROM),
+    part("LPC1227", Lpc1200, CortexM0, FLASH_128K, &[sectors(32, 4 * KB)], &[region(0x1000_0000, 8 * KB)], CORTEX_M_BOOT_ROM),
+    // LPC1300
+    part("LPC1311", Lpc1300, CortexM3, &[region(0, 8 * KB)], &[sectors(2, 4 * KB)], &[region(0x1000_0000, 4 * KB)], CORTEX_M_BOOT_ROM),
+    part("LPC1313", Lpc1300, CortexM3, FLASH_32K, SECTORS_4K_X8, &[region(0x1000_0000, 8 * KB)], CORTEX_M_BOOT_ROM),
+    part("LPC1342", Lpc1300, CortexM3, &[region(0, 16 * KB)], &[sectors(4, 4 * KB)], &[region(0x1000_0000, 4 * KB)], CORTEX_M_USB_BOOT_ROM),
+    part("LPC1343", Lpc1300, CortexM3, FLASH_32K, SECTORS_4K_X8, &[region(0x1000_0000, 8 * KB)], CORTEX_M_USB_BOOT_ROM),
+    part("LPC1345", Lpc1300, CortexM3, FLASH_32K, SECTORS_4K_X8, &[region(0x1000_0000, 8 * KB), region(0x2000_4000, 2 * KB)], CORTEX_M_USB_BOOT_ROM),
+    part("LPC1347", Lpc1300, CortexM3, FLASH_64K, SECTORS_4K_X16, &[region(0x1000_0000, 8 * KB), region(0x2000_0000, 2 * KB), region(0x2000_4000, 2 * KB)], CORTEX_M_USB_BOOT_ROM),
+    // LPC1500
+    part("LPC1517", Lpc1500, CortexM3, FLASH_64K, SECTORS_4K_X16, &[region(0x0200_0000, 12 * KB)], CORTEX_M_CAN_BOOT_ROM),
+    part("LPC1518", Lpc1500, CortexM3, FLASH_128K, &[sectors(32, 4 * KB)], &[region(0x0200_0000, 20 * KB)], CORTEX_M_CAN_BOOT_ROM),
+    part("LPC1519", Lpc1500, CortexM3, FLASH_256K, &[sectors(64, 4 * KB)], &[region(0x0200_0000, 36 * KB)], CORTEX_M_CAN_BOOT_ROM),
+    part("LPC1547", Lpc1500, CortexM3, FLASH_64K, SECTORS_4K_X16, &[region(0x0200_0000, 12 * KB)], CORTEX_M_USB_BOOT_ROM),
+    part("LPC1548", Lpc1500, CortexM3, FLASH_128K, &[sectors(32, 4 * KB)], &[region(0x0200_0000, 20 * KB)], CORTEX_M_USB_BOOT_ROM),
+    part("LPC1549", Lpc1500, CortexM3, FLASH_256K, &[sectors(64, 4 * KB)], &[region(0x0200_0000, 36 * KB)], CORTEX_M_USB_BOOT_ROM),
+    // LPC1700
+    part("LPC1751", Lpc1700, CortexM3, FLASH_32K, SECTORS_4K_X8, &[region(0x1000_0000, 8 * KB)], CORTEX_M_BOOT_ROM),
+    part("LPC1752", Lpc1700, CortexM3, FLASH_64K, SECTORS_4K_X16, &[region(0x1000_0000, 16 * KB)], CORTEX_M_BOOT_ROM),
+    part("LPC1754", Lpc1700, CortexM3, FLASH_128K, LPC17_SECTORS_128K, LPC17_RAM_16K_16K, CORTEX_M_BOOT_ROM),
+    part("LPC1756", Lpc1700, CortexM3, FLASH_256K, LPC17_SECTORS_256K, LPC17_RAM_16K_16K, CORTEX_M_BOOT_ROM),
+    part("LPC1758", Lpc1700, CortexM3, FLASH_512K, LPC17_SECTORS_512K, LPC17_RAM_32K_32K, CORTEX_M_BOOT_ROM),
+    part("LPC1759", Lpc1700, CortexM3, FLASH_512K, LPC17_SECTORS_512K, LPC17_RAM_32K_32K, CORTEX_M_BOOT_ROM),
+    part("LPC1763", Lpc1700, CortexM3, FLASH_256K, LPC17_SECTORS_256K, LPC17_RAM_32K_32K, CORTEX_M_BOOT_ROM),
+    part("LPC1764", Lpc1700, CortexM3, FLASH_128K, LPC17_SECTORS_128K, LPC17_RAM_16K_16K, CORTEX_M_BOOT_ROM),
+    part("LPC1765", Lpc1700, CortexM3, FLASH_256K, LPC17_SECTORS_256K, LPC17_RAM_32K_32K, CORTEX_M_BOOT_ROM),
+    part("LPC1766", Lpc1700, CortexM3, FLASH_256K, LPC17_SECTORS_256K, LPC17_RAM_32K_32K, CORTEX_M_BOOT_ROM),
+    part("LPC1767", Lpc1700, CortexM3, FLASH_512K, LPC17_SECTORS_512K, LPC17_RAM_32K_32K, CORTEX_M_BOOT_ROM),
+    part("LPC1768", Lpc1700, CortexM3, FLASH_512K, LPC17_SECTORS_512K, LPC17_RAM_32K_32K, CORTEX_M_BOOT_ROM),
+    part("LPC1769", Lpc1700, CortexM3, FLASH_512K, LPC17_SECTORS_512K, LPC17_RAM_32K_32K, CORTEX_M_BOOT_ROM),
+    part("LPC1774", Lpc1700, CortexM3, FLASH_128K, LPC17_SECTORS_128K, &[region(0x1000_0000, 32 * KB), region(0x2000_0000, 8 * KB)], CORTEX_M_BOOT_ROM),
+    part("LPC1778", Lpc1700, CortexM3, FLASH_512K, LPC17_SECTORS_512K, LPC178X_RAM, CORTEX_M_BOOT_ROM),
+    part("LPC1788", Lpc1700, CortexM3, FLASH_512K, LPC17_SECTORS_512K, LPC178X_RAM, CORTEX_M_BOOT_ROM),
+    // LPC1800
+    part("LPC1810", Lpc1800, CortexM3, &[], &[], LPC18_RAM_200K, FLASHLESS_BOOT_ROM),
+    part("LPC1820", Lpc1800, CortexM3, &[], &[], LPC18_RAM_200K, FLASHLESS_BOOT_ROM),
+    part("LPC1830", Lpc1800, CortexM3, &[], &[], LPC18_RAM_200K, FLASHLESS_BOOT_ROM),
+    part("LPC1833", Lpc1800, CortexM3, FLASH_2X256K, LPC18_SECTORS_256K, LPC18_RAM_104K, CORTEX_M_USB_BOOT_ROM),
+    part("LPC1837", Lpc1800, CortexM3, FLASH_2X512K, LPC18_SECTORS_512K, LPC18_RAM_136K, CORTEX_M_USB_BOOT_ROM),
+    part("LPC1850", Lpc1800, CortexM3, &[], &[], LPC18_RAM_200K, FLASHLESS_BOOT_ROM),
+    part("LPC1853", Lpc1800, CortexM3, FLASH_2X256K, LPC18_SECTORS_256K, LPC18_RAM_104K, CORTEX_M_USB_BOOT_ROM),
+    part("LPC1857", Lpc1800, CortexM3, FLASH_2X512K, LPC18_SECTORS_512K, LPC18_RAM_136K, CORTEX_M_USB_BOOT_ROM),
+    part("LPC18S10", Lpc1800, CortexM3, &[], &[], LPC18_RAM_200K, FLASHLESS_BOOT_ROM),
+    part("LPC18S30", Lpc1800, CortexM3, &[], &[], LPC18_RAM_200K, FLASHLESS_BOOT_ROM),
+    part("LPC18S50", Lpc1800, CortexM3, &[], &[], LPC18_RAM_200K, FLASHLESS_BOOT_ROM),
+    // LPC2000
+    part("LPC2101", Lpc2000, Arm7Tdmi, &[region(0, 8 * KB)], &[sectors(2, 4 * KB)], &[region(0x4000_0000, 2 * KB)], ARM7_BOOT_ROM),
+    part("LPC2102", Lpc2000, Arm7Tdmi, &[region(0, 16 * KB)], &[sectors(4, 4 * KB)], &[region(0x4000_0000, 4 * KB)], ARM7_BOOT_ROM),
+    part("LPC2103", Lpc2000, Arm7Tdmi, FLASH_32K, SECTORS_4K_X8, &[region(0x4000_0000, 8 * KB)], ARM7_BOOT_ROM),
+    part("LPC2104", Lpc2000, Arm7Tdmi, FLASH_128K, LPC210X_SECTORS_128K, &[region(0x4000_0000, 16 * KB)], ARM7_BOOT_ROM),
+    part("LPC2105", Lpc2000, Arm7Tdmi, FLASH_128K, LPC210X_SECTORS_128K, &[region(0x4000_0000, 32 * KB)], ARM7_BOOT_ROM),
+    part("LPC2106", Lpc2000, Arm7Tdmi, FLASH_128K, LPC210X_SECTORS_128K, &[region(0x4000_0000, 64 * KB)], ARM7_BOOT_ROM),
+    part("LPC2119", Lpc2000, Arm7Tdmi, FLASH_128K, LPC210X_SECTORS_128K, &[region(0x4000_0000, 16 * KB)], ARM7_BOOT_ROM),
+    part("LPC2129", Lpc2000, Arm7Tdmi, FLASH_256K, LPC22_SECTORS_256K, &[region(0x4000_0000, 16 * KB)], ARM7_BOOT_ROM),
+    part("LPC2131", Lpc2000, Arm7Tdmi, FLASH_32K, SECTORS_4K_X8, &[region(0x4000_0000, 8 * KB)], ARM7_BOOT_ROM),
+    part("LPC2132", Lpc2000, Arm7Tdmi, FLASH_64K, LPC21_SECTORS_64K, &[region(0x4000_0000, 16 * KB)], ARM7_BOOT_ROM),
+    part("LPC2134", Lpc2000, Arm7Tdmi, FLASH_128K, LPC21_SECTORS_128K, &[region(0x4000_0000, 16 * KB)], ARM7_BOOT_ROM),
+    part("LPC2136", Lpc2000, Arm7Tdmi, FLASH_256K, LPC21_SECTORS_256K, &[region(0x4000_0000, 32 * KB)], ARM7_BOOT_ROM),
+    part("LPC2138", Lpc2000, Arm7Tdmi, FLASH_512K, LPC21_SECTORS_512K, &[region(0x4000_0000, 32 * KB)], ARM7_BOOT_ROM),
+    part("LPC2141", Lpc2000, Arm7Tdmi, FLASH_32K, SECTORS_4K_X8, &[region(0x4000_0000, 8 * KB)], ARM7_BOOT_ROM),
+    part("LPC2142", Lpc2000, Arm7Tdmi, FLASH_64K, LPC21_SECTORS_64K, &[region(0x4000_0000, 16 * KB)], ARM7_BOOT_ROM),
+    part("LPC2144", Lpc2000, Arm7Tdmi, FLASH_128K, LPC21_SECTORS_128K, &[region(0x4000_0000, 16 * KB)], ARM7_BOOT_ROM),
+    part("LPC2146", Lpc2000, Arm7Tdmi, FLASH_256K, LPC21_SECTORS_256K, LPC214X_USB_RAM, ARM7_BOOT_ROM),
+    part("LPC2148", Lpc2000, Arm7Tdmi, FLASH_512K, LPC21_SECTORS_512K, LPC214X_USB_RAM, ARM7_BOOT_ROM),
+    part("LPC2194", Lpc2000, Arm7Tdmi, FLASH_256K, LPC22_SECTORS_256K, &[region(0x4000_0000, 16 * KB)], ARM7_BOOT_ROM),
+    part("LPC2212", Lpc2000, Arm7Tdmi, FLASH_128K, LPC210X_SECTORS_128K, &[region(0x4000_0000, 16 * KB)], ARM7_BOOT_ROM),
+    part("LPC2214", Lpc2000, Arm7Tdmi, FLASH_256K, LPC22_SECTORS_256K, &[region(0x4000_0000, 16 * KB)], ARM7_BOOT_ROM),
+    part("LPC2292", Lpc2000, Arm7Tdmi, FLASH_256K, LPC22_SECTORS_256K, &[region(0x4000_0000, 16 * KB)], ARM7_BOOT_ROM),
+    part("LPC2294", Lpc2000, Arm7Tdmi, FLASH_256K, LPC22_SECTORS_256K, &[region(0x4000_0000, 16 * KB)], ARM7_BOOT_ROM),
+    part("LPC2364", Lpc2000, Arm7Tdmi, FLASH_128K, LPC21_SECTORS_128K, &[region(0x4000_0000, 8 * KB), region(0x7FD0_0000, 8 * KB), region(0x7FE0_0000, 16 * KB)], ARM7_BOOT_ROM),
+    part("LPC2366", Lpc2000, Arm7Tdmi, FLASH_256K, LPC21_SECTORS_256K, LPC23_RAM_32K, ARM7_BOOT_ROM),
+    part("LPC2368", Lpc2000, Arm7Tdmi, FLASH_512K, LPC21_SECTORS_512K, LPC23_RAM_32K, ARM7_BOOT_ROM),
+    part("LPC2378", Lpc2000, Arm7Tdmi, FLASH_512K, LPC21_SECTORS_512K, LPC23_RAM_32K, ARM7_BOOT_ROM),
+    part("LPC2387", Lpc2000, Arm7Tdmi, FLASH_512K, LPC21_SECTORS_512K, LPC23_RAM_64K, ARM7_BOOT_ROM),
+    part("LPC2388", Lpc2000, Arm7Tdmi, FLASH_512K, LPC21_SECTORS_512K, LPC23_RAM_64K, ARM7_BOOT_ROM),
+    part("LPC2468", Lpc2000, Arm7Tdmi, FLASH_512K, LPC21_SECTORS_512K, LPC23_RAM_64K, ARM7_BOOT_ROM),
+    part("LPC2478", Lpc2000, Arm7Tdmi, FLASH_512K, LPC21_SECTORS_512K, LPC23_RAM_64K, ARM7_BOOT_ROM),
+    // LPC2900
+    part("LPC2917", Lpc2900, Arm968Es, &[region(0x2000_0000, 512 * KB)], LPC29_SECTORS_512K, LPC29_RAM, ARM9_BOOT_ROM),
+    part("LPC2919", Lpc2900, Arm968Es, &[region(0x2000_0000, 768 * KB)], LPC29_SECTORS_768K, LPC29_RAM, ARM9_BOOT_ROM),
+    part("LPC2939", Lpc2900, Arm968Es, &[region(0x2000_0000, 768 * KB)], LPC29_SECTORS_768K, LPC29_RAM, ARM9_BOOT_ROM),
+    // LPC3000
+    part("LPC3130", Lpc3000, Arm926Ejs, &[], &[], &[region(0x1102_8000, 96 * KB)], ARM9_BOOT_ROM),
+    part("LPC3131", Lpc3000, Arm926Ejs, &[], &[], &[region(0x1102_8000, 192 * KB)], ARM9_BOOT_ROM),
+    part("LPC3141", Lpc3000, Arm926Ejs, &[], &[], &[region(0x1102_8000, 192 * KB)], ARM9_BOOT_ROM),
+    part("LPC3143", Lpc3000, Arm926Ejs, &[], &[], &[region(0x1102_8000, 192 * KB)], ARM9_BOOT_ROM),
+    part("LPC3152", Lpc3000, Arm926Ejs, &[], &[], &[region(0x1102_8000, 192 * KB)], ARM9_BOOT_ROM),
+    part("LPC3154", Lpc3000, Arm926Ejs, &[], &[], &[region(0x1102_8000, 192 * KB)], ARM9_BOOT_ROM),
+    part("LPC3180", Lpc3000, Arm926Ejs, &[], &[], &[region(0x0800_0000, 64 * KB)], ARM9_BOOT_ROM),
+    part("LPC3220", Lpc3000, Arm926Ejs, &[], &[], &[region(0x0800_0000, 128 * KB)], ARM9_BOOT_ROM),
+    part("LPC3230", Lpc3000, Arm926Ejs, &[], &[], &[region(0x0800_0000, 256 * KB)], ARM9_BOOT_ROM),
+    part("LPC3240", Lpc3000, Arm926Ejs, &[], &[], &[region(0x0800_0000, 256 * KB)], ARM9_BOOT_ROM),
+    part("LPC3250", Lpc3000, Arm926Ejs, &[], &[], &[region(0x0800_0000, 256 * KB)], ARM9_BOOT_ROM),
+    // LPC4000
+    part("LPC4074", Lpc4000, CortexM4, FLASH_128K, LPC17_SECTORS_128K, &[region(0x1000_0000, 32 * KB), region(0x2000_0000, 8 * KB)], CORTEX_M_BOOT_ROM),
+    part("LPC4078", Lpc4000, CortexM4, FLASH_512K, LPC17_SECTORS_512K, LPC178X_RAM, CORTEX_M_BOOT_ROM),
+    part("LPC4088", Lpc4000, CortexM4, FLASH_512K, LPC17_SECTORS_512K, LPC178X_RAM, CORTEX_M_BOOT_ROM),
+    // LPC4300
+    part("LPC4310", Lpc4300, CortexM4, &[], &[], LPC18_RAM_200K, FLASHLESS_BOOT_ROM),
+    part("LPC4320", Lpc4300, CortexM4, &[], &[], LPC18_RAM_200K, FLASHLESS_BOOT_ROM),
+    part("LPC4330", Lpc4300, CortexM4, &[], &[], LPC43_RAM_264K, FLASHLESS_BOOT_ROM),
+    part("LPC4333", Lpc4300, CortexM4, FLASH_2X256K, LPC18_SECTORS_256K, LPC18_RAM_104K, CORTEX_M_USB_BOOT_ROM),
+    part("LPC4337", Lpc4300, CortexM4, FLASH_2X512K, LPC18_SECTORS_512K, LPC18_RAM_136K, CORTEX_M_USB_BOOT_ROM),
+    part("LPC4350", Lpc4300, CortexM4, &[], &[], LPC43_RAM_264K, FLASHLESS_BOOT_ROM),
+    part("LPC4353", Lpc4300, CortexM4, FLASH_2X256K, LPC18_SECTORS_256K, LPC18_RAM_104K, CORTEX_M_USB_BOOT_ROM),
+    part("LPC4357", Lpc4300, CortexM4, FLASH_2X512K, LPC18_SECTORS_512K, LPC18_RAM_136K, CORTEX_M_USB_BOOT_ROM),
+    part("LPC4367", Lpc4300, CortexM4, FLASH_2X512K, LPC18_SECTORS_512K, LPC18_RAM_136K, CORTEX_M_USB_BOOT_ROM),
+    part("LPC4370", Lpc4300, CortexM4, &[], &[], LPC4370_RAM, FLASHLESS_BOOT_ROM),
+    part("LPC43S20", Lpc4300, CortexM4, &[], &[], LPC18_RAM_200K, FLASHLESS_BOOT_ROM),
+    part("LPC43S30", Lpc4300, CortexM4, &[], &[], LPC43_RAM_264K, FLASHLESS_BOOT_ROM),
+    part("LPC43S37", Lpc4300, CortexM4, FLASH_2X512K, LPC18_SECTORS_512K, LPC18_RAM_136K, CORTEX_M_USB_BOOT_ROM),
+    part("LPC43S50", Lpc4300, CortexM4, &[], &[], LPC43_RAM_264K, FLASHLESS_BOOT_ROM),
+    part("LPC43S57", Lpc4300, CortexM4, FLASH_2X512K, LPC18_SECTORS_512K, LPC18_RAM_136K, CORTEX_M_USB_BOOT_ROM),
+    part("LPC43S67", Lpc4300, CortexM4, FLASH_2X512K, LPC18_SECTORS_512K, LPC18_RAM_136K, CORTEX_M_USB_BOOT_ROM),
+    // LPC5400
+    part("LPC51U68", Lpc5400, CortexM0Plus, FLASH_256K, SECTORS_32K_X8, LPC51U68_RAM, LPC5_BOOT_ROM),
+    part("LPC54005", Lpc5400, CortexM4, &[], &[], LPC540XX_RAM, LPC5_BOOT_ROM),
+    part("LPC54016", Lpc5400, CortexM4, &[], &[], LPC540XX_RAM, LPC5_BOOT_ROM),
+    part("LPC54018", Lpc5400, CortexM4, &[], &[], LPC540XX_RAM, LPC5_BOOT_ROM),
+    part("LPC54101", Lpc5400, CortexM4, FLASH_512K, SECTORS_32K_X16, LPC5410X_RAM, LPC5_BOOT_ROM),
+    part("LPC54102", Lpc5400, CortexM4, FLASH_512K, SECTORS_32K_X16, LPC5410X_RAM, LPC5_BOOT_ROM),
+    part("LPC54113", Lpc5400, CortexM4, FLASH_256K, SECTORS_32K_X8, LPC5411X_RAM, LPC5_BOOT_ROM),
+    part("LPC54114", Lpc5400, CortexM4, FLASH_256K, SECTORS_32K_X8, LPC5411X_RAM, LPC5_BOOT_ROM),
+    part("LPC54S005", Lpc5400, CortexM4, &[], &[], LPC540XX_RAM, LPC5_BOOT_ROM),
+    part("LPC54S016", Lpc5400, CortexM4, &[], &[], LPC540XX_RAM, LPC5_BOOT_ROM),
+    part("LPC54S018", Lpc5400, CortexM4, &[], &[], LPC540XX_RAM, LPC5_BOOT_ROM),
+    part("LPC54605", Lpc5400, CortexM4, FLASH_512K, SECTORS_32K_X16, LPC546XX_RAM, LPC5_BOOT_ROM),
+    part("LPC54606", Lpc5400, CortexM4, FLASH_512K, SECTORS_32K_X16, LPC546XX_RAM, LPC5_BOOT_ROM),
+    part("LPC54607", Lpc5400, CortexM4, FLASH_512K, SECTORS_32K_X16, LPC546XX_RAM, LPC5_BOOT_ROM),
+    part("LPC54608", Lpc5400, CortexM4, FLASH_512K, SECTORS_32K_X16, LPC546XX_RAM, LPC5_BOOT_ROM),
+    part("LPC54616", Lpc5400, CortexM4, FLASH_512K, SECTORS_32K_X16, LPC546XX_RAM, LPC5_BOOT_ROM),
+    part("LPC54618", Lpc5400, CortexM4, FLASH_512K, SECTORS_32K_X16, LPC546XX_RAM, LPC5_BOOT_ROM),
+    part("LPC54628", Lpc5400, CortexM4, FLASH_512K, SECTORS_32K_X16, LPC546XX_RAM, LPC5_BOOT_ROM),
+    // LPC5500
+    part("LPC5502", Lpc5500, CortexM33, LPC55_FLASH_64K, &[sectors(2, 32 * KB)], LPC551X_RAM, LPC5_BOOT_ROM),
+    part("LPC5504", Lpc5500, CortexM33, LPC55_FLASH_128K, &[sectors(4, 32 * KB)], LPC551X_RAM, LPC5_BOOT_ROM),
+    part("LPC5506", Lpc5500, CortexM33, LPC55_FLASH_256K, SECTORS_32K_X8, LPC551X_RAM, LPC5_BOOT_ROM),
+    part("LPC5512", Lpc5500, CortexM33, LPC55_FLASH_128K, &[sectors(4, 32 * KB)], LPC551X_RAM, LPC5_BOOT_ROM),
+    part("LPC5514", Lpc5500, CortexM33, LPC55_FLASH_256K, SECTORS_32K_X8, LPC551X_RAM, LPC5_BOOT_ROM),
+    part("LPC5516", Lpc5500, CortexM33, LPC55_FLASH_256K, SECTORS_32K_X8, LPC551X_RAM, LPC5_BOOT_ROM),
+    part("LPC5526", Lpc5500, CortexM33, LPC55_FLASH_256K, SECTORS_32K_X8, LPC552X_RAM_144K, LPC5_BOOT_ROM),
+    part("LPC5528", Lpc5500, CortexM33, LPC55_FLASH_512K, SECTORS_32K_X16, LPC552X_RAM_256K, LPC5_BOOT_ROM),
+    part("LPC55S06", Lpc5500, CortexM33, LPC55_FLASH_256K, SECTORS_32K_X8, LPC551X_RAM, LPC5_BOOT_ROM),
+    part("LPC55S14", Lpc5500, CortexM33, LPC55_FLASH_256K, SECTORS_32K_X8, LPC551X_RAM, LPC5_BOOT_ROM),
+    part("LPC55S16", Lpc5500, CortexM33, LPC55_FLASH_256K, SECTORS_32K_X8, LPC551X_RAM, LPC5_BOOT_ROM),
+    part("LPC55S26", Lpc5500, CortexM33, LPC55_FLASH_256K, SECTORS_32K_X8, LPC552X_RAM_144K, LPC5_BOOT_ROM),
+    part("LPC55S28", Lpc5500, CortexM33, LPC55_FLASH_512K, SECTORS_32K_X16, LPC552X_RAM_256K, LPC5_BOOT_ROM),
+    part("LPC55S36", Lpc5500, CortexM33, LPC55_FLASH_256K, &[sectors(32, 8 * KB)], LPC553X_RAM, LPC5_BOOT_ROM),
+    part("LPC55S66", Lpc5500, CortexM33, LPC55_FLASH_256K, SECTORS_32K_X8, LPC55S6X_RAM_144K, LPC5_BOOT_ROM),
+    part("LPC55S69", Lpc5500, CortexM33, LPC55_FLASH_640K, SECTORS_32K_X20, LPC55S6X_RAM_320K, LPC5_BOOT_ROM),
+];
+
+/// Get a part by its part number (e.g. LPC1768, or LPC1768FBD100).
+pub fn get_part_by_name(part_number: &str) -> Option<&'static Part> {
+    PARTS
+        .iter()
+        .filter(|part| part.matches(part_number))
+        .max_by_key(|part| part.name.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn part_lookup() {
+        assert_eq!(get_part_by_name("lpc1768fbd100").unwrap().name, "LPC1768");
+        assert_eq!(get_part_by_name("LPC55S69JBD100").unwrap().name, "LPC55S69");
+        assert!(get_part_by_name("LPC17680").is_none());
+        assert_eq!(get_part_by_name("LPC18S10FET100").unwrap().name, "LPC18S10");
+        assert_eq!(get_part_by_name("LPC43S57JBD208").unwrap().name, "LPC43S57");
+    }
+
+    #[test]
+    fn part_number_prefix() {
+        assert!(get_part_by_name("LPC2458").is_none());
+        assert_eq!(Family::from_part_number("LPC2458"), Some(Family::Lpc2000));
+        assert_eq!(Family::from_part_number("lpc2917"), Some(Family::Lpc2900));
+        assert_eq!(Family::from_part_number("LPC1114"), Some(Family::Lpc1100));
+        assert_eq!(Family::from_part_number("LPC51U68"), Some(Family::Lpc5400));
+        assert_eq!(Family::from_part_number("LPC2"), None);
+        assert_eq!(Family::from_part_number("STM32F103"), None);
+        assert_eq!(
+            crate::get_processor_checksum_info_by_name("LPC2458").map(|info| info.cpu_family),
+            Some("LPC2")
+        );
+        assert_eq!(get_part_by_name("LPC2129").unwrap().family, Family::Lpc2000);
+        assert_eq!(
+            get_part_by_name("LPC1227FBD64/301").unwrap().family,
+            Family::Lpc1200
+        );
+    }
+
+    #[test]
+    fn sector_layouts() {
+        for part in PARTS {
+            let bank_size = part.flash.first().map_or(0, |bank| bank.size);
+            let sectors_size = part
+                .sectors
+                .iter()
+                .map(|group| group.count * group.size)
+                .sum::<u32>();
+
+            // The ARM7 boot ROM hides the last sectors of the 512 KB parts.
+            assert!(sectors_size <= bank_size, "{}", part.name);
+            assert!(sectors_size + 8 * KB >= bank_size, "{}", part.name);
+        }
+
+        let part = get_part_by_name("LPC1768").unwrap();
+
+        assert_eq!(part.sectors, &[sectors(16, 4 * KB), sectors(14, 32 * KB)]);
+        assert!(part.boot_rom.uart_isp && part.boot_rom.iap && !part.boot_rom.usb_isp);
+        assert!(get_part_by_name("LPC11C24").unwrap().boot_rom.can_isp);
+        assert!(!get_part_by_name("LPC3250").unwrap().boot_rom.uart_isp);
+    }
+
+    #[test]
+    fn deprecated_lpc1000_name() {
+        assert_eq!(Family::from_name("LPC1000"), Some(Family::Lpc1700));
+        assert_eq!(
+            crate::get_processor_checksum_info_by_name("LPC1000").map(|info| info.cpu_family),
+            Some("LPC1")
+        );
+    }
+
+    #[test]
+    fn secure_aliases() {
+        let part = get_part_by_name("LPC55S69").unwrap();
+
+        assert_eq!(part.flash_size(), 640 * KB);
+        assert_eq!(part.ram_size(), 304 * KB);
+        assert!(part.flash.iter().any(|bank| bank.contains(0x1000_0400)));
+        assert!(part.ram.iter().any(|region| region.contains(0x3000_1000)));
+        assert!(part.ram.iter().any(|region| region.contains(0x1400_0000)));
+        assert!(Family::Lpc5500
+            .flash_bases(part.flash_base())
+            .eq([0, 0x1000_0000]));
+        assert!(Family::Lpc1700.flash_bases(Some(0)).eq([0]));
+    }
+}
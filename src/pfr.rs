@@ -0,0 +1,627 @@
+//! Protected flash region pages of the LPC55S6x.
+//!
+//! The Customer Manufacturing Programming Area (CMPA) holds the boot configuration, the root key
+//! table hash and the secure boot settings, while the Customer Field Programmable Area (CFPA)
+//! holds the monotonic version counters and the field debug policy. Both are 512 bytes pages,
+//! whose content is checked against the SHA-256 digest stored at their end.
+//!
+//! The pages are described in TOML or JSON, a decoded page giving back its description.
+
+use crate::signed::{to_hex, Hash, HASH_SIZE};
+use crate::{Error, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::convert::TryInto;
+
+/// Size in bytes of a protected flash region page.
+pub const PAGE_SIZE: usize = 512;
+
+/// Offset of the SHA-256 digest of the page content.
+pub const DIGEST_OFFSET: usize = 0x1E0;
+
+/// Address of the CMPA page.
+pub const CMPA_ADDRESS: u32 = 0x9_E400;
+
+/// Address of the CFPA scratch page, copied by the BootROM to the oldest of the ping and pong pages.
+pub const CFPA_ADDRESS: u32 = 0x9_DE00;
+
+/// Debug access permissions of the DCFG_CC_SOCU registers, with their bit.
+pub const DEBUG_PERMISSIONS: &[(&str, u32)] = &[
+    ("niden", 0),
+    ("dbgen", 1),
+    ("spniden", 2),
+    ("spiden", 3),
+    ("tapen", 4),
+    ("cpu1dbgen", 5),
+    ("isp_cmd_en", 6),
+    ("fa_cmd_en", 7),
+    ("me_cmd_en", 8),
+    ("cpu1niden", 9),
+    ("uuid_check", 15),
+];
+
+/// ISP interfaces entered by default, with their DEFAULT_ISP_MODE value.
+const ISP_MODES: &[(&str, u32)] = &[
+    ("auto", 0),
+    ("usb-hid", 1),
+    ("uart", 2),
+    ("spi", 3),
+    ("i2c", 4),
+    ("disabled", 7),
+];
+
+/// Core clocks at boot, with their BOOT_SPEED value.
+const BOOT_SPEEDS: &[(&str, u32)] = &[("nmpa", 0), ("96mhz", 1), ("48mhz", 2)];
+
+/// TrustZone-M modes, with their TZM_IMAGE_TYPE value.
+const TZM_IMAGE_TYPES: &[(&str, u32)] = &[
+    ("header", 0),
+    ("disabled", 1),
+    ("enabled", 2),
+    ("preset", 3),
+];
+
+fn error<T: Into<String>>(message: T) -> Error {
+    Error::InvalidProtectedPage(message.into())
+}
+
+fn read_word(page: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(page[offset..offset + 4].try_into().unwrap())
+}
+
+fn write_word(page: &mut [u8], offset: usize, value: u32) {
+    page[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+fn field(word: u32, shift: u32, width: u32) -> u32 {
+    (word >> shift) & ((1 << width) - 1)
+}
+
+/// Get the value of a named setting, a value being also accepted as is.
+fn encode_choice(choices: &[(&str, u32)], setting: &str, name: &str) -> Result<u32> {
+    choices
+        .iter()
+        .find(|(choice, _)| choice.eq_ignore_ascii_case(name))
+        .map(|(_, value)| *value)
+        .or_else(|| name.parse().ok())
+        .ok_or_else(|| error(format!("unknown {} \"{}\"", setting, name)))
+}
+
+/// Get the name of a setting value, unknown values being kept as is.
+fn decode_choice(choices: &[(&str, u32)], value: u32) -> String {
+    choices
+        .iter()
+        .find(|(_, choice)| *choice == value)
+        .map_or_else(|| value.to_string(), |(name, _)| name.to_string())
+}
+
+/// Name of the explicit empty debug permission set, programming the register with every access
+/// denied instead of leaving it erased.
+const DEBUG_NONE: &str = "none";
+
+/// Encode debug permissions, the upper half word holding their inverse.
+///
+/// No permission leaves the register erased, for the default policy of the BootROM.
+fn encode_debug(setting: &str, permissions: &[String]) -> Result<u32> {
+    match permissions {
+        [] => return Ok(0),
+        [permission] if permission.eq_ignore_ascii_case(DEBUG_NONE) => return Ok(0xFFFF_0000),
+        _ => {}
+    }
+
+    let mut bits = 0;
+
+    for permission in permissions {
+        let bit = encode_choice(DEBUG_PERMISSIONS, setting, permission)?;
+
+        if bit > 15 {
+            return Err(error(format!("unknown {} \"{}\"", setting, permission)));
+        }
+
+        bits |= 1 << bit;
+    }
+
+    Ok(bits | (!bits & 0xFFFF) << 16)
+}
+
+fn decode_debug(setting: &str, word: u32) -> Result<Vec<String>> {
+    // Erased registers use the default policy.
+    if word == 0 {
+        return Ok(Vec::new());
+    }
+
+    if word >> 16 != !word & 0xFFFF {
+        return Err(error(format!(
+            "{} doesn't hold the inverse of its value",
+            setting
+        )));
+    }
+
+    if word & 0xFFFF == 0 {
+        return Ok(vec![DEBUG_NONE.to_string()]);
+    }
+
+    Ok((0..16)
+        .filter(|bit| word & (1 << bit) != 0)
+        .map(|bit| decode_choice(DEBUG_PERMISSIONS, bit))
+        .collect())
+}
+
+fn encode_flag(flag: bool) -> u32 {
+    if flag {
+        0b11
+    } else {
+        0b00
+    }
+}
+
+fn parse_hash(value: &str) -> Result<Hash> {
+    let mut hash = [0; HASH_SIZE];
+
+    if value.len() != hash.len() * 2 || !value.is_ascii() {
+        return Err(error(format!("invalid SHA-256 hash \"{}\"", value)));
+    }
+
+    for (i, byte) in hash.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&value[i * 2..i * 2 + 2], 16)
+            .map_err(|_| error(format!("invalid SHA-256 hash \"{}\"", value)))?;
+    }
+
+    Ok(hash)
+}
+
+/// Compute the SHA-256 digest of the page content.
+pub fn compute_digest(page: &[u8]) -> Hash {
+    Sha256::digest(&page[..DIGEST_OFFSET]).into()
+}
+
+/// Check the size of a page dump and its digest, if any.
+///
+/// Returns whether the page holds a digest.
+fn check_page(page: &[u8]) -> Result<bool> {
+    if page.len() != PAGE_SIZE {
+        return Err(error(format!(
+            "{} bytes page, {} bytes expected",
+            page.len(),
+            PAGE_SIZE
+        )));
+    }
+
+    let digest = &page[DIGEST_OFFSET..DIGEST_OFFSET + HASH_SIZE];
+
+    if digest.iter().all(|byte| *byte == 0) {
+        return Ok(false);
+    }
+
+    let expected = compute_digest(page);
+
+    if digest != expected {
+        return Err(error(format!(
+            "digest mismatch: expected {}, found {}",
+            to_hex(&expected),
+            to_hex(digest)
+        )));
+    }
+
+    Ok(true)
+}
+
+/// Parse a page description, in JSON if it starts with a brace, or in TOML.
+pub fn parse_description<T: DeserializeOwned>(description: &str) -> Result<T> {
+    if description.trim_start().starts_with('{') {
+        serde_json::from_str(description).map_err(|e| error(format!("invalid description: {}", e)))
+    } else {
+        toml::from_str(description).map_err(|e| error(format!("invalid description: {}", e)))
+    }
+}
+
+/// Settings of the Customer Manufacturing Programming Area.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Cmpa {
+    /// The ISP interface entered by default: auto, usb-hid, uart, spi, i2c or disabled.
+    pub default_isp_mode: String,
+    /// The core clock at boot: nmpa, 96mhz or 48mhz.
+    pub boot_speed: String,
+    /// The raw SPI_FLASH_CFG word.
+    pub spi_flash_cfg: u32,
+    /// The USB vendor ID of the ISP.
+    pub usb_vid: u16,
+    /// The USB product ID of the ISP.
+    pub usb_pid: u16,
+    /// The raw SDIO_CFG word.
+    pub sdio_cfg: u32,
+    /// The debug permissions fixed by the CC_SOCU_PIN register, empty to leave it erased, or
+    /// "none" to deny every access.
+    pub debug_pin: Vec<String>,
+    /// The debug permissions granted by default, in the CC_SOCU_DFLT register, encoded as the ones
+    /// fixed.
+    pub debug_default: Vec<String>,
+    /// The raw VENDOR_USAGE word.
+    pub vendor_usage: u32,
+    /// Whether 4096 bits RSA keys are used.
+    pub rsa4k: bool,
+    /// Whether the NXP configuration is included in the DICE computation.
+    pub dice_enc_nxp_cfg: bool,
+    /// Whether the customer configuration is included in the DICE computation.
+    pub dice_cust_cfg: bool,
+    /// Whether the DICE computation is skipped.
+    pub skip_dice: bool,
+    /// The TrustZone-M mode: header, disabled, enabled or preset.
+    pub tzm_image_type: String,
+    /// Whether the PUF key code can't be set anymore.
+    pub block_set_key: bool,
+    /// Whether the PUF enrollment is blocked.
+    pub block_enroll: bool,
+    /// Whether the secure version counter is included in the DICE computation.
+    pub dice_inc_sec_epoch: bool,
+    /// Whether only signed images are booted.
+    pub secure_boot: bool,
+    /// The raw PRINCE_BASE_ADDR word.
+    pub prince_base_addr: u32,
+    /// The raw PRINCE_SR words of the three PRINCE regions.
+    pub prince_sr: [u32; 3],
+    /// The root key table hash, in hexadecimal.
+    pub rotkh: String,
+    /// Whether the digest is written, which permanently locks the page.
+    pub seal: bool,
+}
+
+impl Default for Cmpa {
+    fn default() -> Self {
+        Cmpa {
+            default_isp_mode: "auto".to_string(),
+            boot_speed: "nmpa".to_string(),
+            spi_flash_cfg: 0,
+            usb_vid: 0,
+            usb_pid: 0,
+            sdio_cfg: 0,
+            debug_pin: Vec::new(),
+            debug_default: Vec::new(),
+            vendor_usage: 0,
+            rsa4k: false,
+            dice_enc_nxp_cfg: false,
+            dice_cust_cfg: false,
+            skip_dice: false,
+            tzm_image_type: "header".to_string(),
+            block_set_key: false,
+            block_enroll: false,
+            dice_inc_sec_epoch: false,
+            secure_boot: false,
+            prince_base_addr: 0,
+            prince_sr: [0; 3],
+            rotkh: to_hex(&[0; HASH_SIZE]),
+            seal: false,
+        }
+    }
+}
+
+impl Cmpa {
+    /// Offset of the BOOT_CFG word.
+    const BOOT_CFG_OFFSET: usize = 0x00;
+    /// Offset of the SPI_FLASH_CFG word.
+    const SPI_FLASH_CFG_OFFSET: usize = 0x04;
+    /// Offset of the USB_ID word.
+    const USB_ID_OFFSET: usize = 0x08;
+    /// Offset of the SDIO_CFG word.
+    const SDIO_CFG_OFFSET: usize = 0x0C;
+    /// Offset of the CC_SOCU_PIN word.
+    const CC_SOCU_PIN_OFFSET: usize = 0x10;
+    /// Offset of the CC_SOCU_DFLT word.
+    const CC_SOCU_DFLT_OFFSET: usize = 0x14;
+    /// Offset of the VENDOR_USAGE word.
+    const VENDOR_USAGE_OFFSET: usize = 0x18;
+    /// Offset of the SECURE_BOOT_CFG word.
+    const SECURE_BOOT_CFG_OFFSET: usize = 0x1C;
+    /// Offset of the PRINCE_BASE_ADDR word.
+    const PRINCE_BASE_ADDR_OFFSET: usize = 0x20;
+    /// Offset of the PRINCE_SR words.
+    const PRINCE_SR_OFFSET: usize = 0x24;
+    /// Offset of the root key table hash.
+    const ROTKH_OFFSET: usize = 0x50;
+
+    /// Decode a CMPA page dump, or build the page from its description.
+    pub fn load(data: &[u8]) -> Result<Self> {
+        // Descriptions are text, while pages always hold cleared reserved words.
+        if data.contains(&0) {
+            return Self::parse(data);
+        }
+
+        let description =
+            std::str::from_utf8(data).map_err(|_| error("description isn't UTF-8 text"))?;
+
+        parse_description(description)
+    }
+
+    /// Decode a CMPA page dump, checking its digest.
+    pub fn parse(page: &[u8]) -> Result<Self> {
+        let seal = check_page(page)?;
+        let boot_cfg = read_word(page, Self::BOOT_CFG_OFFSET);
+        let usb_id = read_word(page, Self::USB_ID_OFFSET);
+        let secure_boot_cfg = read_word(page, Self::SECURE_BOOT_CFG_OFFSET);
+        let flag = |shift| field(secure_boot_cfg, shift, 2) != 0;
+
+        Ok(Cmpa {
+            default_isp_mode: decode_choice(ISP_MODES, field(boot_cfg, 4, 3)),
+            boot_speed: decode_choice(BOOT_SPEEDS, field(boot_cfg, 7, 2)),
+            spi_flash_cfg: read_word(page, Self::SPI_FLASH_CFG_OFFSET),
+            usb_vid: usb_id as u16,
+            usb_pid: (usb_id >> 16) as u16,
+            sdio_cfg: read_word(page, Self::SDIO_CFG_OFFSET),
+            debug_pin: decode_debug("CC_SOCU_PIN", read_word(page, Self::CC_SOCU_PIN_OFFSET))?,
+            debug_default: decode_debug(
+                "CC_SOCU_DFLT",
+                read_word(page, Self::CC_SOCU_DFLT_OFFSET),
+            )?,
+            vendor_usage: read_word(page, Self::VENDOR_USAGE_OFFSET),
+            rsa4k: flag(0),
+            dice_enc_nxp_cfg: flag(2),
+            dice_cust_cfg: flag(4),
+            skip_dice: flag(6),
+            tzm_image_type: decode_choice(TZM_IMAGE_TYPES, field(secure_boot_cfg, 8, 2)),
+            block_set_key: flag(10),
+            block_enroll: flag(12),
+            dice_inc_sec_epoch: flag(14),
+            secure_boot: flag(30),
+            prince_base_addr: read_word(page, Self::PRINCE_BASE_ADDR_OFFSET),
+            prince_sr: [0, 1, 2].map(|i| read_word(page, Self::PRINCE_SR_OFFSET + i * 4)),
+            rotkh: to_hex(&page[Self::ROTKH_OFFSET..Self::ROTKH_OFFSET + HASH_SIZE]),
+            seal,
+        })
+    }
+
+    /// Encode the CMPA page, with its digest if sealed.
+    pub fn to_bytes(&self) -> Result<[u8; PAGE_SIZE]> {
+        let mut page = [0; PAGE_SIZE];
+        let isp_mode = encode_choice(ISP_MODES, "ISP mode", &self.default_isp_mode)?;
+        let boot_speed = encode_choice(BOOT_SPEEDS, "boot speed", &self.boot_speed)?;
+        let tzm_image_type =
+            encode_choice(TZM_IMAGE_TYPES, "TrustZone-M mode", &self.tzm_image_type)?;
+
+        if isp_mode > 0b111 || boot_speed > 0b11 || tzm_image_type > 0b11 {
+            return Err(error("boot configuration value out of range"));
+        }
+
+        write_word(
+            &mut page,
+            Self::BOOT_CFG_OFFSET,
+            isp_mode << 4 | boot_speed << 7,
+        );
+        write_word(&mut page, Self::SPI_FLASH_CFG_OFFSET, self.spi_flash_cfg);
+        write_word(
+            &mut page,
+            Self::USB_ID_OFFSET,
+            u32::from(self.usb_vid) | u32::from(self.usb_pid) << 16,
+        );
+        write_word(&mut page, Self::SDIO_CFG_OFFSET, self.sdio_cfg);
+        write_word(
+            &mut page,
+            Self::CC_SOCU_PIN_OFFSET,
+            encode_debug("debug permission", &self.debug_pin)?,
+        );
+        write_word(
+            &mut page,
+            Self::CC_SOCU_DFLT_OFFSET,
+            encode_debug("debug permission", &self.debug_default)?,
+        );
+        write_word(&mut page, Self::VENDOR_USAGE_OFFSET, self.vendor_usage);
+        write_word(
+            &mut page,
+            Self::SECURE_BOOT_CFG_OFFSET,
+            encode_flag(self.rsa4k)
+                | encode_flag(self.dice_enc_nxp_cfg) << 2
+                | encode_flag(self.dice_cust_cfg) << 4
+                | encode_flag(self.skip_dice) << 6
+                | tzm_image_type << 8
+                | encode_flag(self.block_set_key) << 10
+                | encode_flag(self.block_enroll) << 12
+                | encode_flag(self.dice_inc_sec_epoch) << 14
+                | encode_flag(self.secure_boot) << 30,
+        );
+        write_word(
+            &mut page,
+            Self::PRINCE_BASE_ADDR_OFFSET,
+            self.prince_base_addr,
+        );
+
+        for (i, sr) in self.prince_sr.iter().enumerate() {
+            write_word(&mut page, Self::PRINCE_SR_OFFSET + i * 4, *sr);
+        }
+
+        page[Self::ROTKH_OFFSET..Self::ROTKH_OFFSET + HASH_SIZE]
+            .copy_from_slice(&parse_hash(&self.rotkh)?);
+
+        if self.seal {
+            let digest = compute_digest(&page);
+            page[DIGEST_OFFSET..DIGEST_OFFSET + HASH_SIZE].copy_from_slice(&digest);
+        }
+
+        Ok(page)
+    }
+}
+
+/// Settings of the Customer Field Programmable Area.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Cfpa {
+    /// The version of the page, which must increase on each update.
+    pub version: u32,
+    /// The monotonic version counter of the secure firmware.
+    pub secure_fw_version: u32,
+    /// The monotonic version counter of the non-secure firmware.
+    pub non_secure_fw_version: u32,
+    /// The raw IMAGE_KEY_REVOKE word.
+    pub image_key_revoke: u32,
+    /// The raw ROTKH_REVOKE word.
+    pub rotkh_revoke: u32,
+    /// The raw VENDOR_USAGE word.
+    pub vendor_usage: u32,
+    /// The debug permissions fixed by the DCFG_CC_SOCU_NS_PIN register, empty to leave it
+    /// erased, or "none" to deny every access.
+    pub debug_pin: Vec<String>,
+    /// The debug permissions granted by default, in the DCFG_CC_SOCU_NS_DFLT register, encoded
+    /// as the ones fixed.
+    pub debug_default: Vec<String>,
+}
+
+impl Cfpa {
+    /// Offset of the VERSION word.
+    const VERSION_OFFSET: usize = 0x04;
+    /// Offset of the S_FW_VERSION word.
+    const SECURE_FW_VERSION_OFFSET: usize = 0x08;
+    /// Offset of the NS_FW_VERSION word.
+    const NON_SECURE_FW_VERSION_OFFSET: usize = 0x0C;
+    /// Offset of the IMAGE_KEY_REVOKE word.
+    const IMAGE_KEY_REVOKE_OFFSET: usize = 0x10;
+    /// Offset of the ROTKH_REVOKE word.
+    const ROTKH_REVOKE_OFFSET: usize = 0x18;
+    /// Offset of the VENDOR_USAGE word.
+    const VENDOR_USAGE_OFFSET: usize = 0x1C;
+    /// Offset of the DCFG_CC_SOCU_NS_PIN word.
+    const DCFG_CC_SOCU_PIN_OFFSET: usize = 0x20;
+    /// Offset of the DCFG_CC_SOCU_NS_DFLT word.
+    const DCFG_CC_SOCU_DFLT_OFFSET: usize = 0x24;
+
+    /// Decode a CFPA page dump, checking its digest.
+    pub fn parse(page: &[u8]) -> Result<Self> {
+        check_page(page)?;
+
+        Ok(Cfpa {
+            version: read_word(page, Self::VERSION_OFFSET),
+            secure_fw_version: read_word(page, Self::SECURE_FW_VERSION_OFFSET),
+            non_secure_fw_version: read_word(page, Self::NON_SECURE_FW_VERSION_OFFSET),
+            image_key_revoke: read_word(page, Self::IMAGE_KEY_REVOKE_OFFSET),
+            rotkh_revoke: read_word(page, Self::ROTKH_REVOKE_OFFSET),
+            vendor_usage: read_word(page, Self::VENDOR_USAGE_OFFSET),
+            debug_pin: decode_debug(
+                "DCFG_CC_SOCU_NS_PIN",
+                read_word(page, Self::DCFG_CC_SOCU_PIN_OFFSET),
+            )?,
+            debug_default: decode_debug(
+                "DCFG_CC_SOCU_NS_DFLT",
+                read_word(page, Self::DCFG_CC_SOCU_DFLT_OFFSET),
+            )?,
+        })
+    }
+
+    /// Encode the CFPA page, with its digest.
+    pub fn to_bytes(&self) -> Result<[u8; PAGE_SIZE]> {
+        let mut page = [0; PAGE_SIZE];
+
+        for (offset, value) in [
+            (Self::VERSION_OFFSET, self.version),
+            (Self::SECURE_FW_VERSION_OFFSET, self.secure_fw_version),
+            (
+                Self::NON_SECURE_FW_VERSION_OFFSET,
+                self.non_secure_fw_version,
+            ),
+            (Self::IMAGE_KEY_REVOKE_OFFSET, self.image_key_revoke),
+            (Self::ROTKH_REVOKE_OFFSET, self.rotkh_revoke),
+            (Self::VENDOR_USAGE_OFFSET, self.vendor_usage),
+            (
+                Self::DCFG_CC_SOCU_PIN_OFFSET,
+                encode_debug("debug permission", &self.debug_pin)?,
+            ),
+            (
+                Self::DCFG_CC_SOCU_DFLT_OFFSET,
+                encode_debug("debug permission", &self.debug_default)?,
+            ),
+        ] {
+            write_word(&mut page, offset, value);
+        }
+
+        let digest = compute_digest(&page);
+        page[DIGEST_OFFSET..DIGEST_OFFSET + HASH_SIZE].copy_from_slice(&digest);
+
+        Ok(page)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn permissions(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn debug_round_trip() {
+        for names in [
+            &[][..],
+            &["none"],
+            &["niden", "dbgen", "uuid_check"],
+            &["isp_cmd_en", "10"],
+        ] {
+            let word = encode_debug("debug permission", &permissions(names)).unwrap();
+
+            assert_eq!(
+                decode_debug("CC_SOCU_PIN", word).unwrap(),
+                permissions(names)
+            );
+        }
+
+        assert_eq!(encode_debug("debug permission", &[]).unwrap(), 0);
+        assert_eq!(
+            encode_debug("debug permission", &permissions(&["none"])).unwrap(),
+            0xFFFF_0000
+        );
+        assert_eq!(
+            encode_debug("debug permission", &permissions(&["dbgen"])).unwrap(),
+            0xFFFD_0002
+        );
+        assert!(encode_debug("debug permission", &permissions(&["16"])).is_err());
+        assert!(decode_debug("CC_SOCU_PIN", 0x0000_0002).is_err());
+    }
+
+    #[test]
+    fn cmpa_round_trip() {
+        let cmpa = Cmpa {
+            default_isp_mode: "usb-hid".to_string(),
+            boot_speed: "96mhz".to_string(),
+            usb_vid: 0x1FC9,
+            usb_pid: 0x0021,
+            debug_pin: permissions(&["niden", "dbgen", "isp_cmd_en"]),
+            debug_default: permissions(&["none"]),
+            rsa4k: true,
+            tzm_image_type: "enabled".to_string(),
+            secure_boot: true,
+            prince_sr: [1, 2, 3],
+            rotkh: to_hex(&[0xA5; HASH_SIZE]),
+            seal: true,
+            ..Cmpa::default()
+        };
+        let page = cmpa.to_bytes().unwrap();
+
+        assert_eq!(Cmpa::parse(&page).unwrap(), cmpa);
+
+        let mut corrupted = page;
+        corrupted[0] ^= 1;
+        assert!(Cmpa::parse(&corrupted).is_err());
+    }
+
+    #[test]
+    fn erased_pages() {
+        let page = [0; PAGE_SIZE];
+
+        assert_eq!(Cmpa::parse(&page).unwrap(), Cmpa::default());
+        assert_eq!(Cmpa::default().to_bytes().unwrap(), page);
+        assert_eq!(Cfpa::parse(&page).unwrap(), Cfpa::default());
+    }
+
+    #[test]
+    fn cfpa_round_trip() {
+        let cfpa = Cfpa {
+            version: 2,
+            secure_fw_version: 3,
+            non_secure_fw_version: 4,
+            debug_default: permissions(&["dbgen", "spiden"]),
+            ..Cfpa::default()
+        };
+        let page = cfpa.to_bytes().unwrap();
+
+        assert_eq!(Cfpa::parse(&page).unwrap(), cfpa);
+        assert_eq!(read_word(&page, Cfpa::DCFG_CC_SOCU_PIN_OFFSET), 0);
+    }
+}
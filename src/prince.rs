@@ -0,0 +1,320 @@
+//! PRINCE on-the-fly flash encryption of the LPC55S6x.
+//!
+//! The flash is split in three PRINCE regions of 256 KB, whose base addresses are set by the
+//! CMPA PRINCE_BASE_ADDR word and whose enabled 8 KB subregions are set by the PRINCE_SR words.
+//! Each region has its own 128 bits key and 64 bits IV, stored in the PUF key store.
+//!
+//! The flash controller uses PRINCE in counter mode: each little-endian 64 bits word is xored
+//! with the encryption of the IV xored with the address of the word, so that encrypting and
+//! decrypting are the same operation.
+
+use crate::image::{BinaryImage, Image};
+use crate::pfr::Cmpa;
+use crate::{Error, Result};
+use core::convert::TryInto;
+
+/// Count of PRINCE regions.
+pub const REGION_COUNT: usize = 3;
+
+/// Size in bytes of a PRINCE region.
+pub const REGION_SIZE: u32 = 0x4_0000;
+
+/// Size in bytes of a PRINCE subregion.
+pub const SUBREGION_SIZE: u32 = 0x2000;
+
+/// Size in bytes of a PRINCE block.
+pub const BLOCK_SIZE: usize = 8;
+
+/// Size in bytes of a region key.
+pub const KEY_SIZE: usize = 16;
+
+/// Size in bytes of a region IV.
+pub const IV_SIZE: usize = 8;
+
+/// Round constants.
+const RC: [u64; 12] = [
+    0x0000_0000_0000_0000,
+    0x1319_8A2E_0370_7344,
+    0xA409_3822_299F_31D0,
+    0x082E_FA98_EC4E_6C89,
+    0x4528_21E6_38D0_1377,
+    0xBE54_66CF_34E9_0C6C,
+    0x7EF8_4F78_FD95_5CB1,
+    0x8584_0851_F1AC_43AA,
+    0xC882_D32F_2532_3C54,
+    0x64A5_1195_E0E3_610D,
+    0xD3B5_A399_CA0C_2399,
+    0xC0AC_29B7_C97C_50DD,
+];
+
+/// S-box.
+const SBOX: [u8; 16] = [
+    0xB, 0xF, 0x3, 0x2, 0xA, 0xC, 0x9, 0x1, 0x6, 0x7, 0x8, 0x0, 0xE, 0x5, 0xD, 0x4,
+];
+
+/// Inverse of the S-box.
+const SBOX_INVERSE: [u8; 16] = [
+    0xB, 0x7, 0x3, 0x2, 0xF, 0xD, 0x8, 0x9, 0xA, 0x6, 0x4, 0x0, 0x5, 0xE, 0xC, 0x1,
+];
+
+/// Nibble permutation of the shift rows step, from the most significant nibble.
+const SHIFT_ROWS: [usize; 16] = [0, 5, 10, 15, 4, 9, 14, 3, 8, 13, 2, 7, 12, 1, 6, 11];
+
+fn substitute(state: u64, sbox: &[u8; 16]) -> u64 {
+    (0..16).fold(0, |result, i| {
+        result | u64::from(sbox[(state >> (i * 4)) as usize & 0xF]) << (i * 4)
+    })
+}
+
+/// Multiply a 16 bits chunk by the M̂0 (offset 0) or M̂1 (offset 1) matrix.
+fn multiply_chunk(chunk: u16, offset: usize) -> u16 {
+    let nibble = |i: usize| (chunk >> (12 - i * 4)) & 0xF;
+
+    (0..4).fold(0, |result, row| {
+        // Each output bit is the sum of the same bit of the input nibbles but one.
+        let nibble_bits = (0..4).fold(0, |bits, bit| {
+            let skipped = (bit + 8 - row - offset) % 4;
+            let mask = 0x8 >> bit;
+
+            (0..4)
+                .filter(|&column| column != skipped)
+                .fold(bits, |bits, column| bits ^ (nibble(column) & mask))
+        });
+
+        result | nibble_bits << (12 - row * 4)
+    })
+}
+
+/// Apply the involutive M' layer.
+fn multiply(state: u64) -> u64 {
+    [0, 1, 1, 0]
+        .iter()
+        .enumerate()
+        .fold(0, |result, (i, &offset)| {
+            let shift = 48 - i * 16;
+
+            result | u64::from(multiply_chunk((state >> shift) as u16, offset)) << shift
+        })
+}
+
+fn shift_rows(state: u64, inverse: bool) -> u64 {
+    let nibble = |i: usize| (state >> (60 - i * 4)) & 0xF;
+
+    (0..16).fold(0, |result, i| {
+        if inverse {
+            result | nibble(i) << (60 - SHIFT_ROWS[i] * 4)
+        } else {
+            result | nibble(SHIFT_ROWS[i]) << (60 - i * 4)
+        }
+    })
+}
+
+/// Encrypt a 64 bits block with the 128 bits key k0 || k1.
+pub fn encrypt_block(block: u64, k0: u64, k1: u64) -> u64 {
+    let k0_prime = k0.rotate_right(1) ^ (k0 >> 63);
+    let mut state = block ^ k0 ^ k1 ^ RC[0];
+
+    for rc in &RC[1..6] {
+        state = shift_rows(multiply(substitute(state, &SBOX)), false) ^ rc ^ k1;
+    }
+
+    state = substitute(multiply(substitute(state, &SBOX)), &SBOX_INVERSE);
+
+    for rc in &RC[6..11] {
+        state = substitute(multiply(shift_rows(state ^ rc ^ k1, true)), &SBOX_INVERSE);
+    }
+
+    state ^ RC[11] ^ k1 ^ k0_prime
+}
+
+/// PRINCE region enabled by the CMPA.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    /// The index of the region.
+    pub index: usize,
+    /// The address of the region.
+    pub base_address: u32,
+    /// The enabled subregions, bit 0 being the first one.
+    pub subregions: u32,
+    /// The first half of the key.
+    pub k0: u64,
+    /// The second half of the key.
+    pub k1: u64,
+    /// The IV.
+    pub iv: u64,
+}
+
+impl Region {
+    /// Create the region of the given index from the CMPA PRINCE_BASE_ADDR and PRINCE_SR words,
+    /// with its key and IV in big-endian order.
+    pub fn new(
+        index: usize,
+        base_addr: u32,
+        subregions: u32,
+        key: &[u8; KEY_SIZE],
+        iv: &[u8; IV_SIZE],
+    ) -> Result<Self> {
+        if index >= REGION_COUNT {
+            return Err(Error::InvalidPrince("only three PRINCE regions"));
+        }
+
+        Ok(Region {
+            index,
+            base_address: (base_addr >> (index * 4) & 0xF) * REGION_SIZE,
+            subregions,
+            k0: u64::from_be_bytes(key[..8].try_into().unwrap()),
+            k1: u64::from_be_bytes(key[8..].try_into().unwrap()),
+            iv: u64::from_be_bytes(*iv),
+        })
+    }
+
+    /// Whether the address is in an enabled subregion.
+    pub fn contains(&self, address: u32) -> bool {
+        address
+            .checked_sub(self.base_address)
+            .filter(|offset| *offset < REGION_SIZE)
+            .is_some_and(|offset| self.subregions >> (offset / SUBREGION_SIZE) & 1 != 0)
+    }
+
+    fn keystream(&self, address: u32) -> u64 {
+        encrypt_block(self.iv ^ u64::from(address), self.k0, self.k1)
+    }
+}
+
+/// Encrypt or decrypt the words of an image located at the given address which are in an
+/// enabled subregion of the regions.
+///
+/// Returns the count of bytes encrypted.
+pub fn apply(regions: &[Region], address: u32, image: &mut [u8]) -> Result<usize> {
+    let mut encrypted = 0;
+    let in_region = |address: u32| regions.iter().find(|region| region.contains(address));
+
+    for (i, block) in image.chunks_mut(BLOCK_SIZE).enumerate() {
+        let block_address = address + (i * BLOCK_SIZE) as u32;
+        let region = match in_region(block_address) {
+            Some(region) => region,
+            None => continue,
+        };
+
+        // Subregions being aligned on blocks, only the image ends may be partial blocks.
+        if !(block_address as usize).is_multiple_of(BLOCK_SIZE) {
+            return Err(Error::InvalidPrince(
+                "image start not aligned on a PRINCE block",
+            ));
+        }
+
+        if block.len() != BLOCK_SIZE {
+            return Err(Error::InvalidPrince(
+                "image end not aligned on a PRINCE block",
+            ));
+        }
+
+        let data = u64::from_le_bytes((&*block).try_into().unwrap());
+        block.copy_from_slice(&(data ^ region.keystream(block_address)).to_le_bytes());
+        encrypted += BLOCK_SIZE;
+    }
+
+    Ok(encrypted)
+}
+
+/// Create the regions enabled by the CMPA, with the keys and IVs given in the region order.
+pub fn enabled_regions(cmpa: &Cmpa, keys: &[[u8; KEY_SIZE + IV_SIZE]]) -> Result<Vec<Region>> {
+    let mut keys = keys.iter();
+    let mut regions = Vec::new();
+
+    for (index, subregions) in cmpa.prince_sr.iter().enumerate() {
+        if *subregions == 0 {
+            continue;
+        }
+
+        let key = keys.next().ok_or(Error::InvalidPrince(
+            "one key file needed per enabled region",
+        ))?;
+
+        regions.push(Region::new(
+            index,
+            cmpa.prince_base_addr,
+            *subregions,
+            key[..KEY_SIZE].try_into().unwrap(),
+            key[KEY_SIZE..].try_into().unwrap(),
+        )?);
+    }
+
+    if keys.next().is_some() {
+        return Err(Error::InvalidPrince("more key files than enabled regions"));
+    }
+
+    Ok(regions)
+}
+
+/// Encrypt or decrypt the words of a binary image in the regions.
+///
+/// Returns the image, and the count of bytes in the regions.
+pub fn apply_image(firmware: &dyn Image, regions: &[Region]) -> Result<(BinaryImage, usize)> {
+    if firmware.format_name() != "binary" {
+        return Err(Error::InvalidImage(
+            "PRINCE encryption only supported for binary images".to_string(),
+        ));
+    }
+
+    let mut data = firmware.read_vec(0, firmware.size())?;
+    let size = apply(regions, firmware.base_address(), &mut data)?;
+
+    Ok((BinaryImage { data }, size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_answers() {
+        // Test vectors of the PRINCE paper, appendix A.
+        for &(block, k0, k1, expected) in &[
+            (0, 0, 0, 0x8186_65AA_0D02_DFDA),
+            (u64::MAX, 0, 0, 0x604A_E6CA_03C2_0ADA),
+            (0, u64::MAX, 0, 0x9FB5_1935_FC3D_F524),
+            (0, 0, u64::MAX, 0x78A5_4CBE_737B_B7EF),
+            (
+                0x0123_4567_89AB_CDEF,
+                0,
+                0xFEDC_BA98_7654_3210,
+                0xAE25_AD3C_A8FA_9CCF,
+            ),
+        ] {
+            assert_eq!(encrypt_block(block, k0, k1), expected);
+        }
+    }
+
+    #[test]
+    fn apply_round_trip() {
+        let key = [0x11; KEY_SIZE];
+        let iv = [0x22; IV_SIZE];
+        // Second region at 0x40000, with its first and third subregions enabled.
+        let regions = [Region::new(1, 0x10, 0b101, &key, &iv).unwrap()];
+        let address = regions[0].base_address;
+        let plain = (0..3 * SUBREGION_SIZE as usize)
+            .map(|i| i as u8)
+            .collect::<Vec<_>>();
+        let mut image = plain.clone();
+
+        assert_eq!(
+            apply(&regions, address, &mut image).unwrap(),
+            2 * SUBREGION_SIZE as usize
+        );
+
+        let subregion = SUBREGION_SIZE as usize;
+        assert_ne!(image[..subregion], plain[..subregion]);
+        assert_eq!(
+            image[subregion..2 * subregion],
+            plain[subregion..2 * subregion]
+        );
+        assert_ne!(image[2 * subregion..], plain[2 * subregion..]);
+
+        apply(&regions, address, &mut image).unwrap();
+        assert_eq!(image, plain);
+
+        assert!(apply(&regions, address + 4, &mut image).is_err());
+        assert!(Region::new(3, 0, 0, &key, &iv).is_err());
+    }
+}
@@ -0,0 +1,49 @@
+//! Machine readable report of the operations done on an image.
+
+use crate::boot_header::BootHeader;
+use crate::lpc54::BootBlock;
+use crate::lpc55::ImageHeader;
+use crate::signature::FlashSignature;
+#[cfg(feature = "secure")]
+use crate::signed::Inspection;
+use serde::Serialize;
+
+/// Report of the checksum operation done on an image.
+#[derive(Debug, Default, Serialize)]
+pub struct Report {
+    /// The processor familly detected.
+    pub family: &'static str,
+    /// The part number matched in the database, if a part number was given.
+    pub part: Option<&'static str>,
+    /// The file format of the image.
+    pub format: &'static str,
+    /// The address of the first byte of the image.
+    pub base_address: u32,
+    /// The count of words summed to compute the checksum.
+    pub words_summed: usize,
+    /// The word position of the checksum value.
+    pub checksum_position: usize,
+    /// The value stored at the checksum position before the operation.
+    pub old_word: u32,
+    /// The checksum computed, written at the checksum position unless only verifying.
+    pub new_word: u32,
+    /// The Code Read Protection level of the image, if the familly uses a CRP word.
+    pub crp: Option<&'static str>,
+    /// The flash signature of the image, if requested.
+    pub flash_signature: Option<FlashSignature>,
+    /// The boot image header of the LPC1800 and LPC4300 famillies, if handled.
+    pub boot_header: Option<BootHeader>,
+    /// The enhanced boot block of the LPC5400 familly.
+    pub boot_block: Option<BootBlock>,
+    /// The image header of the LPC5500 familly.
+    pub image_header: Option<ImageHeader>,
+    /// The root key table hash of the LPC5500 signed images, in hexadecimal.
+    pub root_key_table_hash: Option<String>,
+    /// The content of the LPC5500 signed image, if inspected.
+    #[cfg(feature = "secure")]
+    pub inspection: Option<Inspection>,
+    /// Whether the image file was modified.
+    pub modified: bool,
+    /// The warnings raised during the operation.
+    pub warnings: Vec<String>,
+}
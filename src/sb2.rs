@@ -0,0 +1,971 @@
+//! Secure Binary 2.1 files of the LPC5500 familly.
+//!
+//! A SB2.1 file holds the boot commands (erase, load, jump...) run by the BootROM receive-sb-file
+//! command. The boot sections are encrypted with AES-256-CTR by a data encryption key (DEK) and
+//! authenticated by HMAC-SHA256, both keys being wrapped by the SBKEK programmed in the part.
+//! Signed files also hold the certificate block of signed images and the RSA signature of the
+//! headers.
+//!
+//! The file is made of 16 bytes cipher blocks:
+//! - the image header,
+//! - the section headers, followed by their HMAC,
+//! - the key blob, wrapping the DEK and the HMAC key,
+//! - the certificate block and signature of signed files,
+//! - the boot sections, each starting with its encrypted tag, followed by the HMAC table of the
+//!   encrypted commands.
+
+use crate::checksum;
+use crate::image::Image;
+use crate::signed::{self, CertBlock};
+use crate::{Error, Result};
+use aes::Aes256;
+use aes_kw::KekAes256;
+use ctr::cipher::{KeyIvInit, StreamCipher};
+use hmac::{Hmac, Mac};
+use rsa::traits::PublicKeyParts;
+use rsa::RsaPrivateKey;
+use sha2::Sha256;
+use std::convert::TryInto;
+
+/// Size in bytes of a cipher block.
+pub const BLOCK_SIZE: usize = 16;
+
+/// Size in bytes of the image header.
+pub const HEADER_SIZE: usize = 0x60;
+
+/// Size in bytes of the AES-256 keys.
+pub const KEY_SIZE: usize = 32;
+
+/// Size in bytes of the nonce of the AES-CTR counter.
+pub const NONCE_SIZE: usize = 16;
+
+/// Size in bytes of a section header.
+const SECTION_HEADER_SIZE: usize = 16;
+
+/// Size in bytes of a HMAC-SHA256.
+const HMAC_SIZE: usize = 32;
+
+/// Size in bytes of the key blob, the wrapped keys being padded to a block.
+const KEY_BLOB_SIZE: usize = 80;
+
+/// Count of encrypted command blocks authenticated by each HMAC of a section.
+const HMAC_CHUNK_BLOCKS: usize = 256;
+
+/// First signature of the image header, following the nonce and a reserved word.
+const SIGNATURE1: &[u8; 4] = b"STMP";
+
+/// Second signature of the image header, preceding the timestamp.
+const SIGNATURE2: &[u8; 4] = b"sgtl";
+
+/// Image header flag of signed files.
+pub const FLAG_SIGNED: u16 = 0x8;
+
+/// Section flag of bootable sections.
+const SECTION_BOOTABLE: u32 = 0x1;
+
+/// Tag of the command starting a section.
+const TAG_TAG: u8 = 0x01;
+/// Tag of the load command.
+const TAG_LOAD: u8 = 0x02;
+/// Tag of the jump command.
+const TAG_JUMP: u8 = 0x04;
+/// Tag of the erase command.
+const TAG_ERASE: u8 = 0x07;
+/// Tag of the reset command.
+const TAG_RESET: u8 = 0x08;
+
+/// Flag of the tag of the last section.
+const TAG_LAST: u16 = 0x1;
+/// Flag of the erase command erasing the whole flash.
+const ERASE_ALL: u16 = 0x1;
+/// Flag of the jump command setting the stack pointer.
+const JUMP_STACK_POINTER: u16 = 0x2;
+
+fn error<T: Into<String>>(message: T) -> Error {
+    Error::InvalidSecureBinary(message.into())
+}
+
+fn read_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes(data[offset..offset + 2].try_into().unwrap())
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn blocks(size: usize) -> usize {
+    size.div_ceil(BLOCK_SIZE)
+}
+
+/// Encrypt or decrypt data located at the given block of the file.
+///
+/// The counter of each block is the nonce with the block index added to its last word, read as
+/// a little endian value, as done by elftosb and spsdk.
+fn apply_cipher(dek: &[u8; KEY_SIZE], nonce: &[u8; NONCE_SIZE], block: usize, data: &mut [u8]) {
+    let counter = u32::from_le_bytes(nonce[12..].try_into().unwrap());
+
+    for (i, chunk) in data.chunks_mut(BLOCK_SIZE).enumerate() {
+        let mut iv = *nonce;
+        iv[12..].copy_from_slice(&counter.wrapping_add((block + i) as u32).to_le_bytes());
+
+        ctr::Ctr128BE::<Aes256>::new(dek.into(), &iv.into()).apply_keystream(chunk);
+    }
+}
+
+fn hmac(mac_key: &[u8; KEY_SIZE], data: &[u8]) -> [u8; HMAC_SIZE] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(mac_key).unwrap();
+    mac.update(data);
+
+    mac.finalize().into_bytes().into()
+}
+
+/// Count of HMACs authenticating the given count of encrypted command blocks.
+fn hmac_count(command_blocks: usize) -> usize {
+    command_blocks.div_ceil(HMAC_CHUNK_BLOCKS).max(1)
+}
+
+/// Find the count of HMACs of a section from its length in blocks, without its tag.
+fn section_hmac_count(length: usize) -> Option<usize> {
+    (1..=length / 2).find(|count| hmac_count(length - 2 * count) == *count)
+}
+
+/// Boot command of a section.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Erase the flash area.
+    Erase {
+        /// The address of the area.
+        address: u32,
+        /// The length in bytes of the area.
+        length: u32,
+    },
+    /// Erase the whole flash.
+    EraseAll,
+    /// Program data at the given address.
+    Load {
+        /// The address of the data.
+        address: u32,
+        /// The data.
+        data: Vec<u8>,
+    },
+    /// Call the code at the given address.
+    Jump {
+        /// The address of the code.
+        address: u32,
+        /// The argument passed in R0.
+        argument: u32,
+        /// The stack pointer to set before the jump, if any.
+        stack_pointer: Option<u32>,
+    },
+    /// Reset the part.
+    Reset,
+}
+
+/// Encode a command header, its first byte being the checksum of the others.
+fn command_header(tag: u8, flags: u16, address: u32, count: u32, data: u32) -> [u8; BLOCK_SIZE] {
+    let mut header = [0; BLOCK_SIZE];
+
+    header[1] = tag;
+    header[2..4].copy_from_slice(&flags.to_le_bytes());
+    header[4..8].copy_from_slice(&address.to_le_bytes());
+    header[8..12].copy_from_slice(&count.to_le_bytes());
+    header[12..16].copy_from_slice(&data.to_le_bytes());
+    header[0] = header[1..]
+        .iter()
+        .fold(0x5A, |checksum: u8, byte| checksum.wrapping_add(*byte));
+
+    header
+}
+
+/// Decoded command header.
+struct CommandHeader {
+    tag: u8,
+    flags: u16,
+    address: u32,
+    count: u32,
+    data: u32,
+}
+
+impl CommandHeader {
+    fn parse(header: &[u8]) -> Result<Self> {
+        let checksum = header[1..]
+            .iter()
+            .fold(0x5A, |checksum: u8, byte| checksum.wrapping_add(*byte));
+
+        if header[0] != checksum {
+            return Err(error("invalid command checksum"));
+        }
+
+        Ok(CommandHeader {
+            tag: header[1],
+            flags: read_u16(header, 2),
+            address: read_u32(header, 4),
+            count: read_u32(header, 8),
+            data: read_u32(header, 12),
+        })
+    }
+}
+
+impl Command {
+    /// Encode the command, load data being padded to a block.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            Command::Erase { address, length } => {
+                command_header(TAG_ERASE, 0, *address, *length, 0).to_vec()
+            }
+            Command::EraseAll => command_header(TAG_ERASE, ERASE_ALL, 0, 0, 0).to_vec(),
+            Command::Load { address, data } => {
+                let mut padded = data.clone();
+                padded.resize(blocks(data.len()) * BLOCK_SIZE, 0);
+
+                let mut command = command_header(
+                    TAG_LOAD,
+                    0,
+                    *address,
+                    data.len() as u32,
+                    checksum::crc32(&padded),
+                )
+                .to_vec();
+                command.extend_from_slice(&padded);
+
+                command
+            }
+            Command::Jump {
+                address,
+                argument,
+                stack_pointer,
+            } => command_header(
+                TAG_JUMP,
+                stack_pointer.map_or(0, |_| JUMP_STACK_POINTER),
+                *address,
+                stack_pointer.unwrap_or(0),
+                *argument,
+            )
+            .to_vec(),
+            Command::Reset => command_header(TAG_RESET, 0, 0, 0, 0).to_vec(),
+        }
+    }
+
+    /// Decode the commands of a decrypted section.
+    fn parse_all(mut data: &[u8]) -> Result<Vec<Self>> {
+        let mut commands = Vec::new();
+
+        while !data.is_empty() {
+            let header = CommandHeader::parse(&data[..BLOCK_SIZE])?;
+            data = &data[BLOCK_SIZE..];
+
+            commands.push(match header.tag {
+                TAG_ERASE if header.flags & ERASE_ALL != 0 => Command::EraseAll,
+                TAG_ERASE => Command::Erase {
+                    address: header.address,
+                    length: header.count,
+                },
+                TAG_LOAD => {
+                    let length = blocks(header.count as usize) * BLOCK_SIZE;
+                    let padded = data
+                        .get(..length)
+                        .ok_or_else(|| error("load command data outside the section"))?;
+
+                    if checksum::crc32(padded) != header.data {
+                        return Err(error("load command data CRC mismatch"));
+                    }
+
+                    data = &data[length..];
+
+                    Command::Load {
+                        address: header.address,
+                        data: padded[..header.count as usize].to_vec(),
+                    }
+                }
+                TAG_JUMP => Command::Jump {
+                    address: header.address,
+                    argument: header.data,
+                    stack_pointer: Some(header.count)
+                        .filter(|_| header.flags & JUMP_STACK_POINTER != 0),
+                },
+                TAG_RESET => Command::Reset,
+                tag => return Err(error(format!("unsupported command tag 0x{:02x}", tag))),
+            });
+        }
+
+        Ok(commands)
+    }
+}
+
+/// Boot section, holding the commands run by the BootROM.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    /// The identifier of the section.
+    pub id: u32,
+    /// The commands of the section.
+    pub commands: Vec<Command>,
+}
+
+/// Keys protecting a SB2.1 file.
+#[derive(Clone)]
+pub struct Keys {
+    /// The key encryption key (SBKEK) programmed in the part.
+    pub kek: [u8; KEY_SIZE],
+    /// The data encryption key of the boot sections.
+    pub dek: [u8; KEY_SIZE],
+    /// The HMAC key of the headers and boot sections.
+    pub mac_key: [u8; KEY_SIZE],
+}
+
+impl Keys {
+    /// Generate random data encryption and HMAC keys, wrapped by the given SBKEK.
+    pub fn generate(kek: [u8; KEY_SIZE]) -> Result<Self> {
+        let mut keys = Keys {
+            kek,
+            dek: [0; KEY_SIZE],
+            mac_key: [0; KEY_SIZE],
+        };
+
+        getrandom::getrandom(&mut keys.dek)
+            .and_then(|_| getrandom::getrandom(&mut keys.mac_key))
+            .map_err(|e| error(format!("cannot generate the keys: {}", e)))?;
+
+        Ok(keys)
+    }
+
+    fn wrap(&self) -> [u8; KEY_BLOB_SIZE] {
+        let mut keys = [0; KEY_SIZE * 2];
+        keys[..KEY_SIZE].copy_from_slice(&self.dek);
+        keys[KEY_SIZE..].copy_from_slice(&self.mac_key);
+
+        let mut key_blob = [0; KEY_BLOB_SIZE];
+        KekAes256::new(&self.kek.into())
+            .wrap(&keys, &mut key_blob[..KEY_SIZE * 2 + 8])
+            .unwrap();
+
+        key_blob
+    }
+
+    fn unwrap(kek: [u8; KEY_SIZE], key_blob: &[u8]) -> Result<Self> {
+        let mut keys = [0; KEY_SIZE * 2];
+
+        KekAes256::new(&kek.into())
+            .unwrap(&key_blob[..KEY_SIZE * 2 + 8], &mut keys)
+            .map_err(|_| error("cannot unwrap the keys, wrong SBKEK"))?;
+
+        Ok(Keys {
+            kek,
+            dek: keys[..KEY_SIZE].try_into().unwrap(),
+            mac_key: keys[KEY_SIZE..].try_into().unwrap(),
+        })
+    }
+}
+
+/// Image header of a SB2.1 file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub struct Header {
+    /// The nonce of the AES-CTR counter.
+    pub nonce: [u8; NONCE_SIZE],
+    /// The flags of the file.
+    pub flags: u16,
+    /// The size of the file, in blocks.
+    pub image_blocks: u32,
+    /// The block of the tag of the first boot section.
+    pub first_boot_tag_block: u32,
+    /// The identifier of the first boot section.
+    pub first_boot_section_id: u32,
+    /// The offset in bytes of the certificate block, zero if unsigned.
+    pub cert_block_offset: u32,
+    /// The block of the key blob.
+    pub key_blob_block: u16,
+    /// The maximum count of HMACs of a section.
+    pub max_section_mac_count: u16,
+    /// The creation time, in microseconds since 2000-01-01.
+    pub timestamp: u64,
+    /// The product version, in BCD.
+    pub product_version: [u16; 3],
+    /// The component version, in BCD.
+    pub component_version: [u16; 3],
+    /// The build number.
+    pub build_number: u16,
+}
+
+impl Header {
+    /// Create the header of a file created now, with a random nonce.
+    pub fn new() -> Result<Self> {
+        let mut nonce = [0; NONCE_SIZE];
+        getrandom::getrandom(&mut nonce)
+            .map_err(|e| error(format!("cannot generate the nonce: {}", e)))?;
+
+        // Microseconds between the Unix epoch and 2000-01-01.
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |time| time.as_micros() as u64)
+            .saturating_sub(946_684_800_000_000);
+
+        Ok(Header {
+            nonce,
+            flags: 0,
+            image_blocks: 0,
+            first_boot_tag_block: 0,
+            first_boot_section_id: 0,
+            cert_block_offset: 0,
+            key_blob_block: 0,
+            max_section_mac_count: 0,
+            timestamp,
+            product_version: [0; 3],
+            component_version: [0; 3],
+            build_number: 0,
+        })
+    }
+
+    fn parse(data: &[u8]) -> Result<Self> {
+        let header = data.get(..HEADER_SIZE).ok_or(Error::ImageTooShort {
+            required: HEADER_SIZE,
+            actual: data.len(),
+        })?;
+
+        if &header[0x14..0x18] != SIGNATURE1 || &header[0x34..0x38] != SIGNATURE2 {
+            return Err(error("invalid image header signature"));
+        }
+
+        if header[0x18..0x1A] != [2, 1] {
+            return Err(error(format!(
+                "unsupported version {}.{}",
+                header[0x18], header[0x19]
+            )));
+        }
+
+        if usize::from(read_u16(header, 0x2C)) * BLOCK_SIZE != HEADER_SIZE {
+            return Err(error("invalid image header size"));
+        }
+
+        let version = |offset: usize| [0, 4, 8].map(|i| read_u16(header, offset + i));
+
+        Ok(Header {
+            nonce: header[..NONCE_SIZE].try_into().unwrap(),
+            flags: read_u16(header, 0x1A),
+            image_blocks: read_u32(header, 0x1C),
+            first_boot_tag_block: read_u32(header, 0x20),
+            first_boot_section_id: read_u32(header, 0x24),
+            cert_block_offset: read_u32(header, 0x28),
+            key_blob_block: read_u16(header, 0x2E),
+            max_section_mac_count: read_u16(header, 0x32),
+            timestamp: u64::from_le_bytes(header[0x38..0x40].try_into().unwrap()),
+            product_version: version(0x40),
+            component_version: version(0x4C),
+            build_number: read_u16(header, 0x58),
+        })
+    }
+
+    fn to_bytes(self) -> [u8; HEADER_SIZE] {
+        let mut header = [0; HEADER_SIZE];
+
+        header[..NONCE_SIZE].copy_from_slice(&self.nonce);
+        header[0x14..0x18].copy_from_slice(SIGNATURE1);
+        header[0x18..0x1A].copy_from_slice(&[2, 1]);
+        header[0x1A..0x1C].copy_from_slice(&self.flags.to_le_bytes());
+        header[0x1C..0x20].copy_from_slice(&self.image_blocks.to_le_bytes());
+        header[0x20..0x24].copy_from_slice(&self.first_boot_tag_block.to_le_bytes());
+        header[0x24..0x28].copy_from_slice(&self.first_boot_section_id.to_le_bytes());
+        header[0x28..0x2C].copy_from_slice(&self.cert_block_offset.to_le_bytes());
+        header[0x2C..0x2E].copy_from_slice(&((HEADER_SIZE / BLOCK_SIZE) as u16).to_le_bytes());
+        header[0x2E..0x30].copy_from_slice(&self.key_blob_block.to_le_bytes());
+        header[0x30..0x32].copy_from_slice(&((KEY_BLOB_SIZE / BLOCK_SIZE) as u16).to_le_bytes());
+        header[0x32..0x34].copy_from_slice(&self.max_section_mac_count.to_le_bytes());
+        header[0x34..0x38].copy_from_slice(SIGNATURE2);
+        header[0x38..0x40].copy_from_slice(&self.timestamp.to_le_bytes());
+
+        for i in 0..3 {
+            header[0x40 + i * 4..0x42 + i * 4]
+                .copy_from_slice(&self.product_version[i].to_le_bytes());
+            header[0x4C + i * 4..0x4E + i * 4]
+                .copy_from_slice(&self.component_version[i].to_le_bytes());
+        }
+
+        header[0x58..0x5A].copy_from_slice(&self.build_number.to_le_bytes());
+
+        header
+    }
+}
+
+/// Content of a SB2.1 file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecureBinary {
+    /// The image header, its layout fields being computed when encoding.
+    pub header: Header,
+    /// The boot sections.
+    pub sections: Vec<Section>,
+    /// The certificate block of signed files.
+    pub cert_block: Option<CertBlock>,
+}
+
+impl SecureBinary {
+    /// Encode the file, encrypting its sections, and signing it if a key and certificate
+    /// block are given.
+    pub fn to_bytes(
+        &self,
+        keys: &Keys,
+        signer: Option<(&RsaPrivateKey, &CertBlock)>,
+    ) -> Result<Vec<u8>> {
+        let first_section = self
+            .sections
+            .first()
+            .ok_or_else(|| error("no boot section"))?;
+        let key_blob_offset = HEADER_SIZE + self.sections.len() * SECTION_HEADER_SIZE + HMAC_SIZE;
+        let mut offset = key_blob_offset + KEY_BLOB_SIZE;
+        let mut header = self.header;
+        let mut cert_block = None;
+
+        if let Some((key, cert_block_template)) = signer {
+            let mut signed_cert_block = cert_block_template.clone();
+            signed_cert_block.header.total_image_length = (offset + signed_cert_block.size())
+                .try_into()
+                .map_err(|_| error("file too large"))?;
+
+            header.flags |= FLAG_SIGNED;
+            header.cert_block_offset = offset as u32;
+            offset += signed_cert_block.size() + key.size();
+            cert_block = Some((key, signed_cert_block));
+        } else {
+            header.flags &= !FLAG_SIGNED;
+            header.cert_block_offset = 0;
+        }
+
+        offset = blocks(offset) * BLOCK_SIZE;
+
+        // Encode the commands to lay out the sections.
+        let sections = self
+            .sections
+            .iter()
+            .map(|section| {
+                let commands = section
+                    .commands
+                    .iter()
+                    .flat_map(Command::to_bytes)
+                    .collect::<Vec<_>>();
+                let block = offset / BLOCK_SIZE;
+                let hmac_count = hmac_count(blocks(commands.len()));
+                offset += BLOCK_SIZE + hmac_count * HMAC_SIZE + commands.len();
+
+                (section, block, hmac_count, commands)
+            })
+            .collect::<Vec<_>>();
+
+        header.image_blocks = (offset / BLOCK_SIZE)
+            .try_into()
+            .map_err(|_| error("file too large"))?;
+        header.first_boot_tag_block = sections[0].1 as u32;
+        header.first_boot_section_id = first_section.id;
+        header.key_blob_block = (key_blob_offset / BLOCK_SIZE) as u16;
+        header.max_section_mac_count = sections
+            .iter()
+            .map(|(_, _, hmac_count, _)| *hmac_count as u16)
+            .max()
+            .unwrap();
+
+        let mut data = header.to_bytes().to_vec();
+
+        for (section, block, hmac_count, commands) in &sections {
+            let length = 2 * hmac_count + blocks(commands.len());
+
+            data.extend_from_slice(&section.id.to_le_bytes());
+            data.extend_from_slice(&(*block as u32).to_le_bytes());
+            data.extend_from_slice(&(length as u32).to_le_bytes());
+            data.extend_from_slice(&SECTION_BOOTABLE.to_le_bytes());
+        }
+
+        let headers_hmac = hmac(&keys.mac_key, &data);
+        data.extend_from_slice(&headers_hmac);
+        data.extend_from_slice(&keys.wrap());
+
+        if let Some((key, cert_block)) = &cert_block {
+            data.extend_from_slice(&cert_block.to_bytes());
+
+            let signature = cert_block.sign(&data, key)?;
+            data.extend_from_slice(&signature);
+        }
+
+        data.resize(blocks(data.len()) * BLOCK_SIZE, 0);
+
+        for (i, (section, block, hmac_count, commands)) in sections.iter().enumerate() {
+            let length = 2 * hmac_count + blocks(commands.len());
+            let mut tag = command_header(
+                TAG_TAG,
+                if i == sections.len() - 1 { TAG_LAST } else { 0 },
+                section.id,
+                length as u32,
+                SECTION_BOOTABLE,
+            );
+            apply_cipher(&keys.dek, &header.nonce, *block, &mut tag);
+            data.extend_from_slice(&tag);
+
+            let mut commands = commands.clone();
+            apply_cipher(
+                &keys.dek,
+                &header.nonce,
+                block + 1 + 2 * hmac_count,
+                &mut commands,
+            );
+
+            for chunk in commands.chunks(HMAC_CHUNK_BLOCKS * BLOCK_SIZE) {
+                data.extend_from_slice(&hmac(&keys.mac_key, chunk));
+            }
+
+            // Empty sections still hold a HMAC.
+            if commands.is_empty() {
+                data.extend_from_slice(&hmac(&keys.mac_key, &[]));
+            }
+
+            data.extend_from_slice(&commands);
+        }
+
+        Ok(data)
+    }
+
+    /// Check that the file loads the image at its base address, and the root key table hash of
+    /// signed files if given.
+    pub fn check_image(
+        &self,
+        firmware: &dyn Image,
+        root_key_hash: Option<signed::Hash>,
+    ) -> Result<()> {
+        if let (Some(cert_block), Some(hash)) = (&self.cert_block, root_key_hash) {
+            if cert_block.root_key_table_hash() != hash {
+                return Err(Error::InvalidSignature(format!(
+                    "root key table hash mismatch of the SB2.1 file: expected {}, found {}",
+                    signed::to_hex(&hash),
+                    signed::to_hex(&cert_block.root_key_table_hash())
+                )));
+            }
+        }
+
+        let image = firmware.read_vec(0, firmware.size())?;
+        let loaded = self.sections.iter().any(|section| {
+            section.commands.iter().any(|command| {
+                matches!(command, Command::Load { address, data }
+                    if *address == firmware.base_address() && *data == image)
+            })
+        });
+
+        if !loaded {
+            return Err(error("no load command of the image at its base address"));
+        }
+
+        Ok(())
+    }
+
+    /// Decode a SB2.1 file, checking its HMACs and its signature if signed.
+    ///
+    /// Returns the file with the keys unwrapped by the given SBKEK.
+    pub fn parse(data: &[u8], kek: [u8; KEY_SIZE]) -> Result<(Self, Keys)> {
+        let header = Header::parse(data)?;
+        let key_blob_offset = usize::from(header.key_blob_block) * BLOCK_SIZE;
+
+        if header.image_blocks as usize * BLOCK_SIZE != data.len() {
+            return Err(error("file size doesn't match the image header"));
+        }
+
+        let sections_size = key_blob_offset
+            .checked_sub(HEADER_SIZE + HMAC_SIZE)
+            .filter(|size| size % SECTION_HEADER_SIZE == 0)
+            .ok_or_else(|| error("invalid key blob location"))?;
+        let key_blob = data
+            .get(key_blob_offset..key_blob_offset + KEY_BLOB_SIZE)
+            .ok_or_else(|| error("key blob outside the file"))?;
+        let keys = Keys::unwrap(kek, key_blob)?;
+        let headers_end = key_blob_offset - HMAC_SIZE;
+
+        if hmac(&keys.mac_key, &data[..headers_end])[..] != data[headers_end..key_blob_offset] {
+            return Err(error("headers HMAC mismatch"));
+        }
+
+        let cert_block = if header.flags & FLAG_SIGNED != 0 {
+            let cert_block = CertBlock::parse(data, header.cert_block_offset as usize)?;
+
+            cert_block.verify_chain()?;
+            cert_block.verify_image(data)?;
+
+            Some(cert_block)
+        } else {
+            None
+        };
+
+        let mut sections = Vec::new();
+
+        for section_header in
+            data[HEADER_SIZE..HEADER_SIZE + sections_size].chunks(SECTION_HEADER_SIZE)
+        {
+            let id = read_u32(section_header, 0);
+            let block = read_u32(section_header, 4) as usize;
+            let length = read_u32(section_header, 8) as usize;
+            let section = data
+                .get(block * BLOCK_SIZE..(block + 1 + length) * BLOCK_SIZE)
+                .ok_or_else(|| error(format!("section {} outside the file", id)))?;
+
+            let mut tag = [0; BLOCK_SIZE];
+            tag.copy_from_slice(&section[..BLOCK_SIZE]);
+            apply_cipher(&keys.dek, &header.nonce, block, &mut tag);
+            let tag = CommandHeader::parse(&tag)?;
+
+            if tag.tag != TAG_TAG || tag.address != id || tag.count as usize != length {
+                return Err(error(format!("invalid tag of section {}", id)));
+            }
+
+            let hmac_count = section_hmac_count(length)
+                .ok_or_else(|| error(format!("invalid length of section {}", id)))?;
+            let hmacs = &section[BLOCK_SIZE..BLOCK_SIZE + hmac_count * HMAC_SIZE];
+            let mut commands = section[BLOCK_SIZE + hmac_count * HMAC_SIZE..].to_vec();
+
+            let mut chunks = commands
+                .chunks(HMAC_CHUNK_BLOCKS * BLOCK_SIZE)
+                .collect::<Vec<_>>();
+
+            if chunks.is_empty() {
+                chunks.push(&[]);
+            }
+
+            for (chunk, expected) in chunks.iter().zip(hmacs.chunks(HMAC_SIZE)) {
+                if hmac(&keys.mac_key, chunk)[..] != *expected {
+                    return Err(error(format!("HMAC mismatch in section {}", id)));
+                }
+            }
+
+            apply_cipher(
+                &keys.dek,
+                &header.nonce,
+                block + 1 + 2 * hmac_count,
+                &mut commands,
+            );
+
+            sections.push(Section {
+                id,
+                commands: Command::parse_all(&commands)?,
+            });
+        }
+
+        Ok((
+            SecureBinary {
+                header,
+                sections,
+                cert_block,
+            },
+            keys,
+        ))
+    }
+}
+
+/// Build a SB2.1 file erasing the flash pages of the image, loading it and optionally jumping to
+/// its reset handler, signed if a key and certificate block are given.
+pub fn image_file(
+    firmware: &dyn Image,
+    keys: &Keys,
+    jump: bool,
+    signer: Option<(&RsaPrivateKey, &CertBlock)>,
+) -> Result<Vec<u8>> {
+    const PAGE_SIZE: u32 = 512;
+
+    let address = firmware.base_address();
+    let data = firmware.read_vec(0, firmware.size())?;
+    let erase_address = address / PAGE_SIZE * PAGE_SIZE;
+    let mut commands = vec![
+        Command::Erase {
+            address: erase_address,
+            length: (address - erase_address + data.len() as u32).next_multiple_of(PAGE_SIZE),
+        },
+        Command::Load { address, data },
+    ];
+
+    if jump {
+        let vectors = firmware.read_vec(0, 8)?;
+
+        commands.push(Command::Jump {
+            address: read_u32(&vectors, 4),
+            argument: 0,
+            stack_pointer: Some(read_u32(&vectors, 0)),
+        });
+    }
+
+    let secure_binary = SecureBinary {
+        header: Header::new()?,
+        sections: vec![Section { id: 0, commands }],
+        cert_block: None,
+    };
+
+    secure_binary.to_bytes(keys, signer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEK: [u8; KEY_SIZE] = [0x5A; KEY_SIZE];
+
+    fn secure_binary(load_size: usize) -> SecureBinary {
+        SecureBinary {
+            header: Header::new().unwrap(),
+            sections: vec![Section {
+                id: 0,
+                commands: vec![
+                    Command::Erase {
+                        address: 0,
+                        length: 0x10000,
+                    },
+                    Command::Load {
+                        address: 0,
+                        data: (0..load_size).map(|i| i as u8).collect(),
+                    },
+                    Command::Jump {
+                        address: 0x101,
+                        argument: 0,
+                        stack_pointer: Some(0x2000_8000),
+                    },
+                    Command::Reset,
+                ],
+            }],
+            cert_block: None,
+        }
+    }
+
+    /// Encode and decode the file, returning the encoded file.
+    fn round_trip(secure_binary: &SecureBinary) -> Vec<u8> {
+        let keys = Keys::generate(KEK).unwrap();
+        let data = secure_binary.to_bytes(&keys, None).unwrap();
+        let (parsed, parsed_keys) = SecureBinary::parse(&data, KEK).unwrap();
+
+        assert_eq!(parsed.sections, secure_binary.sections);
+        assert_eq!(parsed.header.nonce, secure_binary.header.nonce);
+        assert_eq!(parsed.header.timestamp, secure_binary.header.timestamp);
+        assert_eq!(parsed.header.image_blocks as usize * BLOCK_SIZE, data.len());
+        assert_eq!(parsed_keys.dek, keys.dek);
+        assert_eq!(parsed_keys.mac_key, keys.mac_key);
+
+        data
+    }
+
+    #[test]
+    fn round_trip_small() {
+        let data = round_trip(&secure_binary(100));
+
+        assert_eq!(
+            SecureBinary::parse(&data, KEK)
+                .unwrap()
+                .0
+                .header
+                .max_section_mac_count,
+            1
+        );
+    }
+
+    #[test]
+    fn round_trip_several_hmacs() {
+        // More than 256 command blocks, needing a second HMAC.
+        let secure_binary = secure_binary(300 * BLOCK_SIZE);
+        let data = round_trip(&secure_binary);
+
+        assert_eq!(
+            SecureBinary::parse(&data, KEK)
+                .unwrap()
+                .0
+                .header
+                .max_section_mac_count,
+            2
+        );
+    }
+
+    #[test]
+    fn round_trip_empty_section() {
+        let mut secure_binary = secure_binary(0);
+        secure_binary.sections.push(Section {
+            id: 1,
+            commands: Vec::new(),
+        });
+
+        round_trip(&secure_binary);
+    }
+
+    #[test]
+    fn wrong_kek() {
+        let data = round_trip(&secure_binary(100));
+        let mut kek = KEK;
+        kek[0] ^= 1;
+
+        assert!(matches!(
+            SecureBinary::parse(&data, kek),
+            Err(Error::InvalidSecureBinary(_))
+        ));
+    }
+
+    #[test]
+    fn flipped_hmac() {
+        let data = round_trip(&secure_binary(300 * BLOCK_SIZE));
+        let (parsed, _) = SecureBinary::parse(&data, KEK).unwrap();
+
+        // The HMAC of the headers, before the key blob.
+        let mut corrupted = data.clone();
+        corrupted[usize::from(parsed.header.key_blob_block) * BLOCK_SIZE - 1] ^= 1;
+        assert!(matches!(
+            SecureBinary::parse(&corrupted, KEK),
+            Err(Error::InvalidSecureBinary(_))
+        ));
+
+        // The second HMAC of the section, after its tag.
+        let mut corrupted = data;
+        corrupted[(parsed.header.first_boot_tag_block as usize + 1) * BLOCK_SIZE + HMAC_SIZE] ^= 1;
+        assert!(matches!(
+            SecureBinary::parse(&corrupted, KEK),
+            Err(Error::InvalidSecureBinary(_))
+        ));
+    }
+
+    #[test]
+    fn known_answer() {
+        // Built by tests/data/sb2_kat.py with the cryptography package, following the layout and
+        // counter of elftosb and spsdk, rather than by these tools.
+        let data = include_bytes!("../tests/data/sb2_kat.sb2");
+        let kek = core::array::from_fn(|i| i as u8);
+        let keys = Keys {
+            kek,
+            dek: core::array::from_fn(|i| 0x20 + i as u8),
+            mac_key: core::array::from_fn(|i| 0x40 + i as u8),
+        };
+        let (parsed, parsed_keys) = SecureBinary::parse(data, kek).unwrap();
+
+        assert_eq!(parsed_keys.dek, keys.dek);
+        assert_eq!(parsed_keys.mac_key, keys.mac_key);
+        assert_eq!(parsed.header.timestamp, 0x0002_9A4E_5C3B_1000);
+        assert_eq!(parsed.header.product_version, [1, 2, 3]);
+        assert_eq!(parsed.header.component_version, [4, 5, 6]);
+        assert_eq!(parsed.header.build_number, 7);
+        assert_eq!(parsed.header.first_boot_tag_block, 14);
+        assert_eq!(
+            parsed.sections,
+            [Section {
+                id: 0,
+                commands: vec![
+                    Command::Erase {
+                        address: 0,
+                        length: 0x1000,
+                    },
+                    Command::Load {
+                        address: 0,
+                        data: (0..40).map(|i| (i * 7) as u8).collect(),
+                    },
+                    Command::Jump {
+                        address: 0x101,
+                        argument: 0x1234_5678,
+                        stack_pointer: Some(0x2000_8000),
+                    },
+                    Command::Reset,
+                ],
+            }]
+        );
+        assert_eq!(parsed.to_bytes(&keys, None).unwrap(), data);
+    }
+
+    #[test]
+    fn hmac_count_of_section_length() {
+        // The HMAC count found when parsing must be the one used when encoding.
+        for command_blocks in 0..2000 {
+            let hmac_count = hmac_count(command_blocks);
+            let length = 2 * hmac_count + command_blocks;
+
+            assert_eq!(section_hmac_count(length), Some(hmac_count));
+        }
+    }
+}
@@ -0,0 +1,236 @@
+//! Flash signature generator of the Cortex-M famillies with a CRP word.
+//!
+//! The flash controller compresses a flash range into a signature with a multiple input
+//! signature register (MISR), read back by the "Read flash signature" ISP and IAP commands.
+//! The LPC800 uses a 32 bits MISR over 32 bits flash words, the other famillies a 128 bits MISR
+//! over 128 bits flash words.
+
+#[cfg(feature = "std")]
+use crate::image::{self, Image};
+#[cfg(feature = "std")]
+use crate::Result;
+use core::convert::TryInto;
+
+/// Width of the flash signature generator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureWidth {
+    /// 32 bits signature of the LPC800 familly.
+    Bits32,
+    /// 128 bits signature.
+    Bits128,
+}
+
+impl SignatureWidth {
+    /// The size in bytes of the flash words, the flash range being aligned on them.
+    pub fn word_size(self) -> usize {
+        match self {
+            SignatureWidth::Bits32 => 4,
+            SignatureWidth::Bits128 => 16,
+        }
+    }
+}
+
+/// Flash signature of an image range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(serde::Serialize))]
+pub struct FlashSignature {
+    /// The address of the first flash word.
+    pub start_address: u32,
+    /// The address following the last flash word.
+    pub end_address: u32,
+    /// The width in bits of the signature.
+    pub bits: u32,
+    /// The signature, the FMSW0 word being the least significant one.
+    pub value: u128,
+}
+
+/// Compute the signature of the given flash words, read as little-endian words.
+///
+/// A partial last word is padded as erased flash.
+pub fn compute_signature(width: SignatureWidth, data: &[u8]) -> u128 {
+    let word_size = width.word_size();
+
+    data.chunks(word_size).fold(0, |signature, chunk| {
+        let mut word = [0xFF; 16];
+        word[..chunk.len()].copy_from_slice(chunk);
+
+        match width {
+            SignatureWidth::Bits32 => {
+                let signature = signature as u32;
+                let feedback =
+                    (signature ^ signature >> 10 ^ signature >> 30 ^ signature >> 31) & 1;
+
+                u128::from(
+                    (signature >> 1 | feedback << 31)
+                        ^ u32::from_le_bytes(word[..4].try_into().unwrap()),
+                )
+            }
+            SignatureWidth::Bits128 => {
+                let feedback = (signature ^ signature >> 2 ^ signature >> 27 ^ signature >> 29) & 1;
+
+                (signature >> 1 | feedback << 127) ^ u128::from_le_bytes(word)
+            }
+        }
+    })
+}
+
+/// Compute the flash signature of the image over the range, the whole image by default, with
+/// the patched vector table if given, the gaps between the data being erased flash.
+///
+/// `handler` is called with a warning if the range isn't inside the image.
+#[cfg(feature = "std")]
+pub fn compute_flash_signature<F: FnMut(String)>(
+    firmware: &dyn Image,
+    vectors: Option<&[u8]>,
+    width: SignatureWidth,
+    range: Option<(u32, u32)>,
+    mut handler: F,
+) -> Result<FlashSignature> {
+    let base_address = firmware.base_address();
+    let mut data = firmware.read_all()?;
+
+    if let Some(vectors) = vectors {
+        data[..vectors.len()].copy_from_slice(vectors);
+    }
+
+    let image_end = base_address + data.len() as u32;
+    let (start_address, end_address) = range.unwrap_or((
+        base_address,
+        base_address + (data.len() as u32).next_multiple_of(width.word_size() as u32),
+    ));
+
+    if start_address < base_address
+        || end_address > image_end.next_multiple_of(width.word_size() as u32)
+    {
+        handler("Flash signature range outside the image, assuming erased flash".to_string());
+    }
+
+    let range_data = (start_address..end_address)
+        .map(|address| {
+            address
+                .checked_sub(base_address)
+                .and_then(|offset| data.get(offset as usize))
+                .copied()
+                .unwrap_or(image::ERASED_BYTE)
+        })
+        .collect::<Vec<_>>();
+
+    Ok(FlashSignature {
+        start_address,
+        end_address,
+        bits: width.word_size() as u32 * 8,
+        value: compute_signature(width, &range_data),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(width: SignatureWidth, values: &[u128]) -> [u8; 64] {
+        let mut data = [0; 64];
+        let size = width.word_size();
+
+        for (chunk, value) in data.chunks_mut(size).zip(values) {
+            chunk.copy_from_slice(&value.to_le_bytes()[..size]);
+        }
+
+        data
+    }
+
+    #[test]
+    fn misr_32_bits() {
+        let width = SignatureWidth::Bits32;
+
+        // The first word is loaded as is, then shifted right with the taps 0, 10, 30 and 31
+        // fed back into bit 31.
+        assert_eq!(
+            compute_signature(width, &words(width, &[0x1234_5678])[..4]),
+            0x1234_5678
+        );
+        assert_eq!(
+            compute_signature(width, &words(width, &[1, 0])[..8]),
+            0x8000_0000
+        );
+        assert_eq!(
+            compute_signature(width, &words(width, &[0x400, 0])[..8]),
+            0x8000_0200
+        );
+        assert_eq!(
+            compute_signature(width, &words(width, &[2, 5])[..8]),
+            0x0000_0004
+        );
+        // A partial word is padded as erased flash.
+        assert_eq!(compute_signature(width, &[0x12, 0x34]), 0xFFFF_3412);
+    }
+
+    #[test]
+    fn misr_128_bits() {
+        let width = SignatureWidth::Bits128;
+
+        // The taps are the bits 0, 2, 27 and 29, fed back into bit 127.
+        assert_eq!(
+            compute_signature(width, &words(width, &[1, 0])[..32]),
+            1 << 127
+        );
+        assert_eq!(
+            compute_signature(width, &words(width, &[4, 0])[..32]),
+            1 << 127 | 2
+        );
+        assert_eq!(
+            compute_signature(width, &words(width, &[1 << 27, 3])[..32]),
+            1 << 127 | 1 << 26 | 3
+        );
+        assert_eq!(compute_signature(width, &[]), 0);
+    }
+
+    #[test]
+    fn misr_is_linear() {
+        let a = (0..64).map(|i: u8| i.wrapping_mul(37)).collect::<Vec<_>>();
+        let b = (0..64).map(|i| i as u8 ^ 0xA5).collect::<Vec<_>>();
+        let xor = a.iter().zip(&b).map(|(a, b)| a ^ b).collect::<Vec<_>>();
+
+        for width in [SignatureWidth::Bits32, SignatureWidth::Bits128] {
+            assert_eq!(
+                compute_signature(width, &a) ^ compute_signature(width, &b),
+                compute_signature(width, &xor)
+            );
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn sparse_image() {
+        // Two records of the first LPC4300 flash bank around a gap, and one of the second bank.
+        let hex = [
+            ":020000041A00E0",
+            ":10000000000102030405060708090A0B0C0D0E0F78",
+            ":10002000101112131415161718191A1B1C1D1E1F58",
+            ":020000041B00DF",
+            ":04000000AAAAAAAA54",
+            ":00000001FF",
+        ]
+        .join("\n");
+        let mut firmware = crate::image::load(hex.into_bytes()).unwrap();
+        firmware.set_flash_base(&[0x1A00_0000]).unwrap();
+
+        let data = (0..16).chain([0xFF; 16]).chain(16..32).collect::<Vec<u8>>();
+        let mut warnings = Vec::new();
+        let flash_signature = compute_flash_signature(
+            firmware.as_ref(),
+            None,
+            SignatureWidth::Bits128,
+            None,
+            |warning| warnings.push(warning),
+        )
+        .unwrap();
+
+        assert!(warnings.is_empty());
+        assert_eq!(flash_signature.start_address, 0x1A00_0000);
+        assert_eq!(flash_signature.end_address, 0x1A00_0030);
+        assert_eq!(
+            flash_signature.value,
+            compute_signature(SignatureWidth::Bits128, &data)
+        );
+    }
+}
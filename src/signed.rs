@@ -0,0 +1,785 @@
+//! Signed images of the LPC5500 familly.
+//!
+//! A signed image is followed by a certificate block, holding the certificate chain and the
+//! table of the root key hashes, then by the RSA signature of the image and certificate block.
+//! The BootROM compares the hash of the root key table (RKTH) with the one programmed in the CMPA.
+
+use crate::image::{BinaryImage, Image};
+use crate::lpc55::{ImageHeader, ImageType, SPECIFIC_HEADER_OFFSET};
+use crate::{Error, Result};
+use rsa::pkcs1::{DecodeRsaPrivateKey, DecodeRsaPublicKey};
+use rsa::pkcs8::{DecodePrivateKey, DecodePublicKey};
+use rsa::traits::PublicKeyParts;
+use rsa::{Pkcs1v15Sign, RsaPrivateKey, RsaPublicKey};
+use sha2::{Digest, Sha256};
+use std::convert::TryInto;
+use x509_cert::der::oid::ObjectIdentifier;
+use x509_cert::der::{Decode, DecodePem, Encode};
+use x509_cert::Certificate;
+
+/// Marker at the start of the certificate block.
+pub const CERT_BLOCK_MAGIC: &[u8; 4] = b"cert";
+
+/// Size in bytes of the certificate block header.
+pub const CERT_BLOCK_HEADER_SIZE: usize = 0x20;
+
+/// Count of entries of the root key table.
+pub const ROOT_KEY_COUNT: usize = 4;
+
+/// Size in bytes of a SHA-256 hash.
+pub const HASH_SIZE: usize = 32;
+
+/// Size in bits of the RSA keys supported by the BootROM.
+const RSA_KEY_SIZES: &[usize] = &[2048, 4096];
+
+/// Certificate signature algorithm supported by the BootROM.
+const SHA256_WITH_RSA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.11");
+
+/// A SHA-256 hash.
+pub type Hash = [u8; HASH_SIZE];
+
+fn error<T: Into<String>>(message: T) -> Error {
+    Error::InvalidSignature(message.into())
+}
+
+fn read_word(data: &[u8], offset: usize) -> Result<u32> {
+    data.get(offset..offset + 4)
+        .map(|word| u32::from_le_bytes(word.try_into().unwrap()))
+        .ok_or(Error::ImageTooShort {
+            required: offset + 4,
+            actual: data.len(),
+        })
+}
+
+/// Format bytes in hexadecimal.
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Compute the hash of a root key, as stored in the root key table.
+pub fn key_hash(key: &RsaPublicKey) -> Hash {
+    Sha256::new()
+        .chain_update(key.n().to_bytes_be())
+        .chain_update(key.e().to_bytes_be())
+        .finalize()
+        .into()
+}
+
+fn check_key_size(key: &RsaPublicKey) -> Result<()> {
+    if !RSA_KEY_SIZES.contains(&(key.size() * 8)) {
+        return Err(error(format!(
+            "unsupported {} bits RSA key, only 2048 and 4096 bits keys are supported",
+            key.size() * 8
+        )));
+    }
+
+    Ok(())
+}
+
+fn parse_certificate(der: &[u8]) -> Result<Certificate> {
+    Certificate::from_der(der).map_err(|e| error(format!("invalid certificate: {}", e)))
+}
+
+/// Get the RSA public key of a DER encoded certificate.
+pub fn certificate_key(der: &[u8]) -> Result<RsaPublicKey> {
+    let spki = parse_certificate(der)?
+        .tbs_certificate
+        .subject_public_key_info
+        .to_der()
+        .map_err(|e| error(format!("invalid certificate: {}", e)))?;
+
+    RsaPublicKey::from_public_key_der(&spki).map_err(|_| error("certificate without RSA key"))
+}
+
+/// Load a certificate in PEM or DER format, returning it DER encoded.
+pub fn load_certificate(data: &[u8]) -> Result<Vec<u8>> {
+    let certificate = if data.starts_with(b"-----BEGIN") {
+        Certificate::from_pem(data)
+    } else {
+        Certificate::from_der(data)
+    }
+    .map_err(|e| error(format!("invalid certificate: {}", e)))?;
+
+    certificate
+        .to_der()
+        .map_err(|e| error(format!("invalid certificate: {}", e)))
+}
+
+/// Load a RSA public key from a certificate, or from a PKCS#1 or SubjectPublicKeyInfo key.
+pub fn load_public_key(data: &[u8]) -> Result<RsaPublicKey> {
+    if let Ok(der) = load_certificate(data) {
+        return certificate_key(&der);
+    }
+
+    core::str::from_utf8(data)
+        .ok()
+        .and_then(|pem| {
+            RsaPublicKey::from_public_key_pem(pem)
+                .or_else(|_| RsaPublicKey::from_pkcs1_pem(pem))
+                .ok()
+        })
+        .or_else(|| RsaPublicKey::from_public_key_der(data).ok())
+        .ok_or_else(|| error("invalid RSA public key"))
+}
+
+/// Load a RSA private key in PKCS#1 or PKCS#8 PEM format.
+pub fn load_private_key(data: &[u8]) -> Result<RsaPrivateKey> {
+    core::str::from_utf8(data)
+        .ok()
+        .and_then(|pem| {
+            RsaPrivateKey::from_pkcs8_pem(pem)
+                .or_else(|_| RsaPrivateKey::from_pkcs1_pem(pem))
+                .ok()
+        })
+        .ok_or_else(|| error("invalid RSA private key"))
+}
+
+/// Check that a DER encoded certificate is signed by the given issuer key.
+pub fn verify_certificate(der: &[u8], issuer: &RsaPublicKey) -> Result<()> {
+    let certificate = parse_certificate(der)?;
+
+    if certificate.signature_algorithm.oid != SHA256_WITH_RSA {
+        return Err(error(format!(
+            "unsupported certificate signature algorithm {}",
+            certificate.signature_algorithm.oid
+        )));
+    }
+
+    let tbs_certificate = certificate
+        .tbs_certificate
+        .to_der()
+        .map_err(|e| error(format!("invalid certificate: {}", e)))?;
+    let signature = certificate
+        .signature
+        .as_bytes()
+        .ok_or_else(|| error("invalid certificate signature"))?;
+
+    issuer
+        .verify(
+            Pkcs1v15Sign::new::<Sha256>(),
+            &Sha256::digest(&tbs_certificate),
+            signature,
+        )
+        .map_err(|_| error("certificate not signed by its issuer"))
+}
+
+/// Header of the certificate block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub struct CertBlockHeader {
+    /// The major version of the certificate block format.
+    pub major_version: u16,
+    /// The minor version of the certificate block format.
+    pub minor_version: u16,
+    /// The size in bytes of the header.
+    pub header_size: u32,
+    /// The flags, unused.
+    pub flags: u32,
+    /// The build number of the image.
+    pub build_number: u32,
+    /// The length in bytes of the image and certificate block, without the signature.
+    pub total_image_length: u32,
+    /// The count of certificates of the chain.
+    pub certificate_count: u32,
+    /// The size in bytes of the certificate table.
+    pub certificate_table_length: u32,
+}
+
+/// Certificate block of a signed image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CertBlock {
+    /// The header of the certificate block.
+    pub header: CertBlockHeader,
+    /// The DER encoded certificates, from the root certificate to the one of the signing key.
+    pub certificates: Vec<Vec<u8>>,
+    /// The hashes of the root keys, unused entries being cleared.
+    pub root_key_hashes: [Hash; ROOT_KEY_COUNT],
+}
+
+impl CertBlock {
+    /// Create the certificate block of the given DER encoded certificate chain.
+    ///
+    /// The root key table holds the given root keys, or only the root certificate key if none.
+    pub fn new(certificates: Vec<Vec<u8>>, root_keys: &[RsaPublicKey]) -> Result<Self> {
+        let root_key = certificate_key(
+            certificates
+                .first()
+                .ok_or_else(|| error("empty certificate chain"))?,
+        )?;
+        let root_keys = if root_keys.is_empty() {
+            core::slice::from_ref(&root_key)
+        } else {
+            root_keys
+        };
+
+        if root_keys.len() > ROOT_KEY_COUNT {
+            return Err(error(format!("more than {} root keys", ROOT_KEY_COUNT)));
+        }
+
+        for der in &certificates {
+            check_key_size(&certificate_key(der)?)?;
+        }
+
+        let mut root_key_hashes = [[0; HASH_SIZE]; ROOT_KEY_COUNT];
+
+        for (hash, key) in root_key_hashes.iter_mut().zip(root_keys) {
+            check_key_size(key)?;
+            *hash = key_hash(key);
+        }
+
+        let certificate_table_length = certificates
+            .iter()
+            .map(|der| 4 + der.len().next_multiple_of(4))
+            .sum::<usize>();
+        let cert_block = CertBlock {
+            header: CertBlockHeader {
+                major_version: 1,
+                minor_version: 0,
+                header_size: CERT_BLOCK_HEADER_SIZE as u32,
+                flags: 0,
+                build_number: 0,
+                total_image_length: 0,
+                certificate_count: certificates.len() as u32,
+                certificate_table_length: certificate_table_length as u32,
+            },
+            certificates,
+            root_key_hashes,
+        };
+
+        cert_block.verify_chain()?;
+
+        Ok(cert_block)
+    }
+
+    /// Parse the certificate block at the given offset of the image.
+    pub fn parse(image: &[u8], offset: usize) -> Result<Self> {
+        if image.get(offset..offset + 4) != Some(&CERT_BLOCK_MAGIC[..]) {
+            return Err(error("invalid certificate block marker"));
+        }
+
+        let version = read_word(image, offset + 0x4)?;
+        let header = CertBlockHeader {
+            major_version: version as u16,
+            minor_version: (version >> 16) as u16,
+            header_size: read_word(image, offset + 0x8)?,
+            flags: read_word(image, offset + 0xC)?,
+            build_number: read_word(image, offset + 0x10)?,
+            total_image_length: read_word(image, offset + 0x14)?,
+            certificate_count: read_word(image, offset + 0x18)?,
+            certificate_table_length: read_word(image, offset + 0x1C)?,
+        };
+
+        if header.major_version != 1 {
+            return Err(error(format!(
+                "unsupported certificate block version {}.{}",
+                header.major_version, header.minor_version
+            )));
+        }
+
+        if header.certificate_count == 0 {
+            return Err(error("empty certificate chain"));
+        }
+
+        let table_offset = offset + header.header_size as usize;
+        let table_end = table_offset + header.certificate_table_length as usize;
+        let mut certificates = Vec::new();
+        let mut position = table_offset;
+
+        for _ in 0..header.certificate_count {
+            let length = read_word(image, position)? as usize;
+            let der = image
+                .get(position + 4..position + 4 + length)
+                .filter(|_| position + 4 + length <= table_end)
+                .ok_or_else(|| error("certificate outside the certificate table"))?;
+
+            certificates.push(der.to_vec());
+            position += 4 + length.next_multiple_of(4);
+        }
+
+        if position != table_end {
+            return Err(error(
+                "certificate table length doesn't match its certificates",
+            ));
+        }
+
+        let mut root_key_hashes = [[0; HASH_SIZE]; ROOT_KEY_COUNT];
+
+        for (i, hash) in root_key_hashes.iter_mut().enumerate() {
+            let start = table_end + i * HASH_SIZE;
+
+            hash.copy_from_slice(image.get(start..start + HASH_SIZE).ok_or(
+                Error::ImageTooShort {
+                    required: start + HASH_SIZE,
+                    actual: image.len(),
+                },
+            )?);
+        }
+
+        Ok(CertBlock {
+            header,
+            certificates,
+            root_key_hashes,
+        })
+    }
+
+    /// The size in bytes of the certificate block.
+    pub fn size(&self) -> usize {
+        self.header.header_size as usize
+            + self.header.certificate_table_length as usize
+            + ROOT_KEY_COUNT * HASH_SIZE
+    }
+
+    /// Encode the certificate block.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut cert_block = CERT_BLOCK_MAGIC.to_vec();
+
+        for value in [
+            u32::from(self.header.major_version) | u32::from(self.header.minor_version) << 16,
+            self.header.header_size,
+            self.header.flags,
+            self.header.build_number,
+            self.header.total_image_length,
+            self.header.certificate_count,
+            self.header.certificate_table_length,
+        ] {
+            cert_block.extend_from_slice(&value.to_le_bytes());
+        }
+
+        for der in &self.certificates {
+            cert_block.extend_from_slice(&(der.len() as u32).to_le_bytes());
+            cert_block.extend_from_slice(der);
+            cert_block.resize(cert_block.len().next_multiple_of(4), 0);
+        }
+
+        for hash in &self.root_key_hashes {
+            cert_block.extend_from_slice(hash);
+        }
+
+        cert_block
+    }
+
+    /// Compute the root key table hash, as programmed in the CMPA.
+    pub fn root_key_table_hash(&self) -> Hash {
+        self.root_key_hashes
+            .iter()
+            .fold(Sha256::new(), |hasher, hash| hasher.chain_update(hash))
+            .finalize()
+            .into()
+    }
+
+    /// Check the root key table hash against the one programmed in the CMPA.
+    pub fn check_root_key_table_hash(&self, expected: &Hash) -> Result<()> {
+        let hash = self.root_key_table_hash();
+
+        if hash != *expected {
+            return Err(error(format!(
+                "root key table hash mismatch: expected {}, found {}",
+                to_hex(expected),
+                to_hex(&hash)
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Get the public key of the last certificate of the chain, used to sign the image.
+    pub fn signing_key(&self) -> Result<RsaPublicKey> {
+        certificate_key(
+            self.certificates
+                .last()
+                .ok_or_else(|| error("empty certificate chain"))?,
+        )
+    }
+
+    /// Check that the root certificate key is in the root key table, and that each certificate
+    /// is signed by the previous one.
+    pub fn verify_chain(&self) -> Result<()> {
+        let root_key = certificate_key(
+            self.certificates
+                .first()
+                .ok_or_else(|| error("empty certificate chain"))?,
+        )?;
+
+        if !self.root_key_hashes.contains(&key_hash(&root_key)) {
+            return Err(error("root certificate key not in the root key table"));
+        }
+
+        for pair in self.certificates.windows(2) {
+            verify_certificate(&pair[1], &certificate_key(&pair[0])?)?;
+        }
+
+        Ok(())
+    }
+
+    /// Check that the private key matches the last certificate of the chain.
+    pub fn check_signing_key(&self, key: &RsaPrivateKey) -> Result<()> {
+        if RsaPublicKey::from(key) != self.signing_key()? {
+            return Err(error(
+                "signing key doesn't match the last certificate of the chain",
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Sign the image ending with this certificate block, returning the signature to append.
+    pub fn sign(&self, image: &[u8], key: &RsaPrivateKey) -> Result<Vec<u8>> {
+        self.check_signing_key(key)?;
+
+        if image.len() != self.header.total_image_length as usize {
+            return Err(error("image length doesn't match the certificate block"));
+        }
+
+        key.sign(Pkcs1v15Sign::new::<Sha256>(), &Sha256::digest(image))
+            .map_err(|e| error(format!("cannot sign the image: {}", e)))
+    }
+
+    /// Check the signature following the image ending with this certificate block.
+    pub fn verify_image(&self, image: &[u8]) -> Result<()> {
+        let length = self.header.total_image_length as usize;
+        let key = self.signing_key()?;
+        let signature = image
+            .get(length..length + key.size())
+            .ok_or(Error::ImageTooShort {
+                required: length + key.size(),
+                actual: image.len(),
+            })?;
+
+        key.verify(
+            Pkcs1v15Sign::new::<Sha256>(),
+            &Sha256::digest(&image[..length]),
+            signature,
+        )
+        .map_err(|_| error("image signature doesn't match"))
+    }
+}
+
+/// Parse the certificate block of a signed image, located by its image header.
+pub fn cert_block(image: &[u8]) -> Result<CertBlock> {
+    let header = ImageHeader::parse(image)?;
+    let cert_block = CertBlock::parse(image, header.specific_header as usize)?;
+
+    if cert_block.header.total_image_length != header.image_length {
+        return Err(error("image length doesn't match the certificate block"));
+    }
+
+    Ok(cert_block)
+}
+
+/// Append the certificate block to an image, storing its offset in the image header.
+///
+/// Returns the offset of the certificate block, the signature being left to be appended.
+pub fn append_cert_block(image: &mut Vec<u8>, cert_block: &mut CertBlock) -> Result<usize> {
+    ImageHeader::parse(image)?;
+
+    image.resize(image.len().next_multiple_of(4), 0);
+
+    let offset = image.len();
+    cert_block.header.total_image_length = (offset + cert_block.size())
+        .try_into()
+        .map_err(|_| error("image too large"))?;
+
+    image[SPECIFIC_HEADER_OFFSET..SPECIFIC_HEADER_OFFSET + 4]
+        .copy_from_slice(&(offset as u32).to_le_bytes());
+    image.extend_from_slice(&cert_block.to_bytes());
+
+    Ok(offset)
+}
+
+/// Append the certificate block to a binary image, replacing the one of signed images.
+///
+/// Returns the image and the offset of the certificate block, `handler` being called with a
+/// warning if the image was signed.
+pub fn append_image_cert_block<F: FnMut(String)>(
+    firmware: &dyn Image,
+    cert_block: &mut CertBlock,
+    mut handler: F,
+) -> Result<(BinaryImage, usize)> {
+    if firmware.format_name() != "binary" {
+        return Err(Error::InvalidImage(
+            "Signed images only supported for binary images".to_string(),
+        ));
+    }
+
+    let mut data = firmware.read_vec(0, firmware.size())?;
+    let header = ImageHeader::parse(&data)?;
+
+    if header.image_type().is_some_and(ImageType::is_signed)
+        && (header.specific_header as usize) < data.len()
+    {
+        handler("Image already signed, replacing its certificate block".to_string());
+
+        data.truncate(header.specific_header as usize);
+    }
+
+    let offset = append_cert_block(&mut data, cert_block)?;
+
+    Ok((BinaryImage { data }, offset))
+}
+
+/// Check the certificate chain and signature of a signed image, and its root key table hash if
+/// given.
+///
+/// Returns the root key table hash, and the error to report once the checksum is verified.
+pub fn verify_image_signature(
+    firmware: &dyn Image,
+    root_key_hash: Option<Hash>,
+) -> Result<(Hash, Option<Error>)> {
+    let data = firmware.read_vec(0, firmware.size())?;
+    let cert_block = cert_block(&data)?;
+    let root_key_table_hash = cert_block.root_key_table_hash();
+
+    let result = cert_block
+        .verify_chain()
+        .and_then(|_| cert_block.verify_image(&data))
+        .and_then(|_| match root_key_hash {
+            Some(hash) => cert_block.check_root_key_table_hash(&hash),
+            None => Ok(()),
+        });
+
+    Ok((root_key_table_hash, result.err()))
+}
+
+/// Description of a certificate of the chain.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct CertificateInfo {
+    /// The subject name.
+    pub subject: String,
+    /// The issuer name.
+    pub issuer: String,
+    /// The serial number, in hexadecimal.
+    pub serial_number: String,
+    /// The start of the validity period.
+    pub not_before: String,
+    /// The end of the validity period.
+    pub not_after: String,
+    /// The size in bits of the RSA key.
+    pub key_size: usize,
+    /// The hash of the RSA key, as stored in the root key table, in hexadecimal.
+    pub key_hash: String,
+}
+
+impl CertificateInfo {
+    /// Describe a DER encoded certificate.
+    pub fn parse(der: &[u8]) -> Result<Self> {
+        let certificate = parse_certificate(der)?.tbs_certificate;
+        let key = certificate_key(der)?;
+
+        Ok(CertificateInfo {
+            subject: certificate.subject.to_string(),
+            issuer: certificate.issuer.to_string(),
+            serial_number: to_hex(certificate.serial_number.as_bytes()),
+            not_before: certificate.validity.not_before.to_string(),
+            not_after: certificate.validity.not_after.to_string(),
+            key_size: key.size() * 8,
+            key_hash: to_hex(&key_hash(&key)),
+        })
+    }
+}
+
+/// Content of a signed image, with the result of the checks done by the BootROM.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct Inspection {
+    /// The offset of the certificate block.
+    pub cert_block_offset: u32,
+    /// The header of the certificate block.
+    pub cert_block: CertBlockHeader,
+    /// The certificates of the chain, from the root certificate.
+    pub certificates: Vec<CertificateInfo>,
+    /// The hashes of the root key table, in hexadecimal.
+    pub root_key_hashes: Vec<String>,
+    /// The root key table hash recomputed, in hexadecimal.
+    pub root_key_table_hash: String,
+    /// The index of the root certificate key in the root key table, if present.
+    pub root_key_index: Option<usize>,
+    /// The signature following the certificate block, in hexadecimal.
+    pub signature: String,
+    /// Why the certificate chain is invalid, if it is.
+    pub chain_error: Option<String>,
+    /// Why the signature is invalid, if it is.
+    pub signature_error: Option<String>,
+}
+
+fn describe(error: Error) -> String {
+    match error {
+        Error::InvalidSignature(message) => message,
+        error => error.to_string(),
+    }
+}
+
+/// Parse the certificate block and signature of a signed image, checking them.
+///
+/// Only a malformed certificate block is an error, the result of the checks being reported.
+pub fn inspect(image: &[u8]) -> Result<Inspection> {
+    let cert_block = cert_block(image)?;
+    let root_key_hash = key_hash(&certificate_key(&cert_block.certificates[0])?);
+    let length = cert_block.header.total_image_length as usize;
+    let signature = cert_block.signing_key().map_or(&[][..], |key| {
+        image.get(length..).map_or(&[][..], |signature| {
+            &signature[..key.size().min(signature.len())]
+        })
+    });
+
+    Ok(Inspection {
+        cert_block_offset: ImageHeader::parse(image)?.specific_header,
+        cert_block: cert_block.header,
+        certificates: cert_block
+            .certificates
+            .iter()
+            .map(|der| CertificateInfo::parse(der))
+            .collect::<Result<_>>()?,
+        root_key_hashes: cert_block
+            .root_key_hashes
+            .iter()
+            .map(|hash| to_hex(hash))
+            .collect(),
+        root_key_table_hash: to_hex(&cert_block.root_key_table_hash()),
+        root_key_index: cert_block
+            .root_key_hashes
+            .iter()
+            .position(|hash| *hash == root_key_hash),
+        signature: to_hex(signature),
+        chain_error: cert_block.verify_chain().err().map(describe),
+        signature_error: cert_block.verify_image(image).err().map(describe),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lpc55::{ImageType, IMAGE_LENGTH_OFFSET, IMAGE_TYPE_OFFSET};
+    use core::str::FromStr;
+    use core::time::Duration;
+    use rsa::pkcs8::EncodePublicKey;
+    use x509_cert::certificate::{TbsCertificate, Version};
+    use x509_cert::der::asn1::{BitString, UtcTime};
+    use x509_cert::der::Any;
+    use x509_cert::name::Name;
+    use x509_cert::serial_number::SerialNumber;
+    use x509_cert::spki::{AlgorithmIdentifierOwned, SubjectPublicKeyInfoOwned};
+    use x509_cert::time::{Time, Validity};
+
+    /// RSA-2048 test keys, generating them being slow without optimizations.
+    const ROOT_KEY: &str = include_str!("../tests/data/root_key.pem");
+    const OTHER_KEY: &str = include_str!("../tests/data/other_key.pem");
+
+    fn test_key(pem: &str) -> RsaPrivateKey {
+        load_private_key(pem.as_bytes()).unwrap()
+    }
+
+    /// Build a self-signed DER encoded certificate of the key.
+    fn self_signed_certificate(key: &RsaPrivateKey) -> Vec<u8> {
+        let algorithm = AlgorithmIdentifierOwned {
+            oid: SHA256_WITH_RSA,
+            parameters: Some(Any::null()),
+        };
+        let name = Name::from_str("CN=root").unwrap();
+        let time =
+            |secs| Time::UtcTime(UtcTime::from_unix_duration(Duration::from_secs(secs)).unwrap());
+        let public_key = RsaPublicKey::from(key).to_public_key_der().unwrap();
+        let tbs_certificate = TbsCertificate {
+            version: Version::V3,
+            serial_number: SerialNumber::new(&[1]).unwrap(),
+            signature: algorithm.clone(),
+            issuer: name.clone(),
+            validity: Validity {
+                not_before: time(946_684_800),
+                not_after: time(2_524_607_999),
+            },
+            subject: name,
+            subject_public_key_info: SubjectPublicKeyInfoOwned::from_der(public_key.as_bytes())
+                .unwrap(),
+            issuer_unique_id: None,
+            subject_unique_id: None,
+            extensions: None,
+        };
+        let signature = key
+            .sign(
+                Pkcs1v15Sign::new::<Sha256>(),
+                &Sha256::digest(tbs_certificate.to_der().unwrap()),
+            )
+            .unwrap();
+
+        Certificate {
+            tbs_certificate,
+            signature_algorithm: algorithm,
+            signature: BitString::from_bytes(&signature).unwrap(),
+        }
+        .to_der()
+        .unwrap()
+    }
+
+    /// Build a signed image with the given certificate block, as done when signing.
+    fn signed_image(cert_block: &mut CertBlock, key: &RsaPrivateKey) -> Vec<u8> {
+        let mut image = (0..0x100).map(|i| i as u8).collect::<Vec<_>>();
+
+        image[IMAGE_TYPE_OFFSET..IMAGE_TYPE_OFFSET + 4]
+            .copy_from_slice(&ImageType::SignedXip.value().to_le_bytes());
+        append_cert_block(&mut image, cert_block).unwrap();
+        image[IMAGE_LENGTH_OFFSET..IMAGE_LENGTH_OFFSET + 4]
+            .copy_from_slice(&cert_block.header.total_image_length.to_le_bytes());
+
+        let signature = cert_block.sign(&image, key).unwrap();
+        image.extend_from_slice(&signature);
+
+        image
+    }
+
+    #[test]
+    fn sign_and_verify() {
+        let key = test_key(ROOT_KEY);
+        let certificate = self_signed_certificate(&key);
+        let mut cert_block = CertBlock::new(vec![certificate], &[]).unwrap();
+        let image = signed_image(&mut cert_block, &key);
+
+        let parsed = super::cert_block(&image).unwrap();
+        assert_eq!(parsed, cert_block);
+        parsed.verify_chain().unwrap();
+        parsed.verify_image(&image).unwrap();
+        parsed
+            .check_root_key_table_hash(&cert_block.root_key_table_hash())
+            .unwrap();
+
+        let inspection = inspect(&image).unwrap();
+        assert_eq!(inspection.root_key_index, Some(0));
+        assert_eq!(inspection.chain_error, None);
+        assert_eq!(inspection.signature_error, None);
+
+        // Tampering with the image breaks the signature.
+        let mut tampered = image.clone();
+        tampered[0x40] ^= 1;
+        assert!(matches!(
+            super::cert_block(&tampered)
+                .unwrap()
+                .verify_image(&tampered),
+            Err(Error::InvalidSignature(_))
+        ));
+
+        // Another root key table doesn't match the hash programmed in the CMPA.
+        let mut other_hash = cert_block.root_key_table_hash();
+        other_hash[0] ^= 1;
+        assert!(matches!(
+            parsed.check_root_key_table_hash(&other_hash),
+            Err(Error::InvalidSignature(_))
+        ));
+    }
+
+    #[test]
+    fn root_key_not_in_table() {
+        let key = test_key(ROOT_KEY);
+        let certificate = self_signed_certificate(&key);
+        let other_key = RsaPublicKey::from(test_key(OTHER_KEY));
+
+        assert!(matches!(
+            CertBlock::new(vec![certificate.clone()], core::slice::from_ref(&other_key)),
+            Err(Error::InvalidSignature(_))
+        ));
+
+        // A certificate block whose root key table doesn't hold the root certificate key.
+        let mut cert_block = CertBlock::new(vec![certificate], &[]).unwrap();
+        cert_block.root_key_hashes[0] = key_hash(&other_key);
+        let image = signed_image(&mut cert_block, &key);
+
+        assert!(super::cert_block(&image).unwrap().verify_chain().is_err());
+        assert_eq!(inspect(&image).unwrap().root_key_index, None);
+    }
+}
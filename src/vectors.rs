@@ -0,0 +1,339 @@
+//! Vector table validation.
+//!
+//! The checksum is computed over whatever words are at the start of the image, so a wrong file
+//! would be patched silently. The vector table is checked against the memory map of the part to
+//! catch these mistakes before patching.
+
+use crate::part::{MemoryRegion, Part};
+use core::convert::TryInto;
+use core::fmt;
+
+/// Names of the Cortex-M vectors checked, starting at the reset vector.
+const CORTEX_M_VECTORS: &[&str] = &["Reset", "NMI", "HardFault"];
+
+/// Names of the ARM7 exception vectors, the reserved one holding the checksum.
+const ARM_VECTORS: &[Option<&str>] = &[
+    Some("Reset"),
+    Some("Undefined"),
+    Some("SWI"),
+    Some("Prefetch Abort"),
+    Some("Data Abort"),
+    None,
+    Some("IRQ"),
+    Some("FIQ"),
+];
+
+/// Size of the ARM7 exception vectors with the literal pool usually following them.
+pub const ARM_VECTOR_AREA_SIZE: usize = 0x40;
+
+/// A decoded ARM exception vector instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArmInstruction {
+    /// B to the target address.
+    Branch(u32),
+    /// LDR PC, [PC, #offset] loading the target from the literal address.
+    LoadPc(u32),
+    /// Any other instruction.
+    Other(u32),
+}
+
+impl ArmInstruction {
+    /// Decode the instruction located at the given address.
+    pub fn decode(address: u32, instruction: u32) -> Self {
+        // The PC reads two instructions ahead.
+        let pc = address.wrapping_add(8);
+
+        if instruction & 0xFF00_0000 == 0xEA00_0000 {
+            let offset = ((instruction << 8) as i32 >> 6) as u32;
+
+            ArmInstruction::Branch(pc.wrapping_add(offset))
+        } else if instruction & 0xFF7F_F000 == 0xE51F_F000 {
+            let offset = instruction & 0xFFF;
+
+            if instruction & (1 << 23) != 0 {
+                ArmInstruction::LoadPc(pc.wrapping_add(offset))
+            } else {
+                ArmInstruction::LoadPc(pc.wrapping_sub(offset))
+            }
+        } else {
+            ArmInstruction::Other(instruction)
+        }
+    }
+
+    /// Returns true if the instruction branches to an exception handler.
+    pub fn is_branch(&self) -> bool {
+        !matches!(self, ArmInstruction::Other(_))
+    }
+}
+
+impl fmt::Display for ArmInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArmInstruction::Branch(target) => write!(f, "B 0x{:08x}", target),
+            ArmInstruction::LoadPc(literal) => write!(f, "LDR PC, [0x{:08x}]", literal),
+            ArmInstruction::Other(instruction) => write!(f, ".word 0x{:08x}", instruction),
+        }
+    }
+}
+
+/// An ARM7 exception vector.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArmVector {
+    /// The name of the vector.
+    pub name: &'static str,
+    /// The address of the vector.
+    pub address: u32,
+    /// The instruction of the vector.
+    pub instruction: ArmInstruction,
+}
+
+impl ArmVector {
+    /// Get the address of the handler, reading the literal of a LDR PC from the image if needed.
+    ///
+    /// Returns `None` if the instruction isn't a branch or if the literal is outside the image.
+    pub fn target(&self, image: &[u8]) -> Option<u32> {
+        match self.instruction {
+            ArmInstruction::Branch(target) => Some(target),
+            ArmInstruction::LoadPc(literal) => read_word(image, literal as usize),
+            ArmInstruction::Other(_) => None,
+        }
+    }
+}
+
+/// Read a word of the image.
+fn read_word(image: &[u8], offset: usize) -> Option<u32> {
+    image
+        .get(offset..offset.checked_add(4)?)
+        .map(|word| u32::from_le_bytes(word.try_into().unwrap()))
+}
+
+/// Decode the ARM7 exception vectors at the start of the image, skipping the reserved one.
+pub fn arm_vectors(image: &[u8]) -> impl Iterator<Item = ArmVector> + '_ {
+    ARM_VECTORS.iter().enumerate().filter_map(move |(i, name)| {
+        let address = i * 4;
+
+        Some(ArmVector {
+            name: (*name)?,
+            address: address as u32,
+            instruction: ArmInstruction::decode(address as u32, read_word(image, address)?),
+        })
+    })
+}
+
+/// An issue found in the vector table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VectorIssue {
+    /// The initial stack pointer isn't inside a RAM region.
+    StackPointerOutsideRam(u32),
+    /// The initial stack pointer isn't 8 bytes aligned.
+    StackPointerUnaligned(u32),
+    /// The handler of a vector isn't inside the flash.
+    HandlerOutsideFlash {
+        /// The name of the vector.
+        vector: &'static str,
+        /// The address of the handler.
+        address: u32,
+    },
+    /// The handler of a vector doesn't have the Thumb bit set.
+    HandlerNotThumb {
+        /// The name of the vector.
+        vector: &'static str,
+        /// The address of the handler.
+        address: u32,
+    },
+    /// An ARM exception vector isn't a branch instruction.
+    NotBranch {
+        /// The name of the vector.
+        vector: &'static str,
+        /// The instruction of the vector.
+        instruction: u32,
+    },
+}
+
+impl VectorIssue {
+    /// Returns true if the issue prevents the image from booting.
+    pub fn is_error(&self) -> bool {
+        match self {
+            VectorIssue::StackPointerOutsideRam(_) => true,
+            VectorIssue::StackPointerUnaligned(_) => false,
+            VectorIssue::HandlerOutsideFlash { vector, .. }
+            | VectorIssue::HandlerNotThumb { vector, .. }
+            | VectorIssue::NotBranch { vector, .. } => *vector == "Reset",
+        }
+    }
+}
+
+impl fmt::Display for VectorIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VectorIssue::StackPointerOutsideRam(address) => {
+                write!(f, "Initial stack pointer 0x{:08x} outside RAM", address)
+            }
+            VectorIssue::StackPointerUnaligned(address) => write!(
+                f,
+                "Initial stack pointer 0x{:08x} not 8 bytes aligned",
+                address
+            ),
+            VectorIssue::HandlerOutsideFlash { vector, address } => {
+                write!(f, "{} handler 0x{:08x} outside flash", vector, address)
+            }
+            VectorIssue::HandlerNotThumb { vector, address } => write!(
+                f,
+                "{} handler 0x{:08x} without the Thumb bit",
+                vector, address
+            ),
+            VectorIssue::NotBranch {
+                vector,
+                instruction,
+            } => write!(
+                f,
+                "{} vector 0x{:08x} isn't a branch instruction",
+                vector, instruction
+            ),
+        }
+    }
+}
+
+/// Returns true if the given address can be the initial value of a full descending stack in the
+/// region.
+fn is_stack_top(region: &MemoryRegion, address: u32) -> bool {
+    address > region.base && u64::from(address) <= u64::from(region.base) + u64::from(region.size)
+}
+
+/// Validate the vector table at the start of the image against the memory map of the part.
+///
+/// `handler` is called for every issue found. Handlers outside flash aren't reported on
+/// flashless parts, nor ARM handlers loaded from outside the image (e.g. from the VIC).
+pub fn validate_vectors<F: FnMut(VectorIssue)>(part: &Part, image: &[u8], mut handler: F) {
+    let mut words = image
+        .chunks_exact(4)
+        .map(|word| u32::from_le_bytes(word.try_into().unwrap()));
+    let in_flash = |address: u32| {
+        part.flash.is_empty() || part.flash.iter().any(|bank| bank.contains(address))
+    };
+
+    if !part.core.is_cortex_m() {
+        for vector in arm_vectors(image) {
+            match (vector.instruction, vector.target(image)) {
+                (ArmInstruction::Other(instruction), _) => handler(VectorIssue::NotBranch {
+                    vector: vector.name,
+                    instruction,
+                }),
+                (_, Some(address)) if !in_flash(address) => {
+                    handler(VectorIssue::HandlerOutsideFlash {
+                        vector: vector.name,
+                        address,
+                    })
+                }
+                _ => {}
+            }
+        }
+
+        return;
+    }
+
+    if let Some(stack_pointer) = words.next() {
+        if !part
+            .ram
+            .iter()
+            .any(|region| is_stack_top(region, stack_pointer))
+        {
+            handler(VectorIssue::StackPointerOutsideRam(stack_pointer));
+        } else if !stack_pointer.is_multiple_of(8) {
+            handler(VectorIssue::StackPointerUnaligned(stack_pointer));
+        }
+    }
+
+    for (address, vector) in words.zip(CORTEX_M_VECTORS.iter().copied()) {
+        if address & 1 == 0 {
+            handler(VectorIssue::HandlerNotThumb { vector, address });
+        }
+
+        if !in_flash(address & !1) {
+            handler(VectorIssue::HandlerOutsideFlash { vector, address });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::part::get_part_by_name;
+
+    /// Build a Cortex-M vector table from its first words.
+    fn vector_table(words: &[u32]) -> [u8; 32] {
+        let mut image = [0; 32];
+
+        for (chunk, word) in image.chunks_mut(4).zip(words) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+
+        image
+    }
+
+    fn issues(part_number: &str, image: &[u8]) -> ([Option<VectorIssue>; 4], usize) {
+        let mut issues = [None; 4];
+        let mut count = 0;
+
+        validate_vectors(get_part_by_name(part_number).unwrap(), image, |issue| {
+            issues[count] = Some(issue);
+            count += 1;
+        });
+
+        (issues, count)
+    }
+
+    #[test]
+    fn non_secure_vector_table() {
+        let image = vector_table(&[0x2004_4000, 0x0000_0101, 0x0000_0201, 0x0000_0301]);
+
+        assert_eq!(issues("LPC55S69", &image).1, 0);
+    }
+
+    #[test]
+    fn secure_vector_table() {
+        let image = vector_table(&[0x3004_4000, 0x1000_0101, 0x1000_0201, 0x1000_0301]);
+
+        assert_eq!(issues("LPC55S69", &image).1, 0);
+        assert_eq!(
+            issues("LPC55S16", &image).0[0],
+            Some(VectorIssue::StackPointerOutsideRam(0x3004_4000))
+        );
+    }
+
+    #[test]
+    fn reset_outside_flash() {
+        let image = vector_table(&[0x1000_8000, 0x1000_0101, 0x0000_0201, 0x0000_0301]);
+        let (issues, count) = issues("LPC1768", &image);
+
+        assert_eq!(count, 1);
+        assert_eq!(
+            issues[0],
+            Some(VectorIssue::HandlerOutsideFlash {
+                vector: "Reset",
+                address: 0x1000_0101
+            })
+        );
+        assert!(issues[0].unwrap().is_error());
+    }
+
+    #[test]
+    fn arm_branch_vectors() {
+        let mut image = [0; ARM_VECTOR_AREA_SIZE];
+
+        // B 0x40, then LDR PC, [PC, #0x18] loading 0x1000 from 0x24.
+        image[..4].copy_from_slice(&0xEA00_000E_u32.to_le_bytes());
+        image[4..8].copy_from_slice(&0xE59F_F018_u32.to_le_bytes());
+        image[0x24..0x28].copy_from_slice(&0x1000_u32.to_le_bytes());
+
+        let mut vectors = arm_vectors(&image);
+        let reset = vectors.next().unwrap();
+        let undefined = vectors.next().unwrap();
+
+        assert_eq!(reset.instruction, ArmInstruction::Branch(0x40));
+        assert_eq!(reset.target(&image), Some(0x40));
+        assert_eq!(undefined.instruction, ArmInstruction::LoadPc(0x24));
+        assert_eq!(undefined.target(&image), Some(0x1000));
+        assert_eq!(vectors.map(|vector| vector.name).nth(3), Some("IRQ"));
+    }
+}
@@ -0,0 +1,89 @@
+#!/usr/bin/env python3
+"""Build the SB2.1 known answer file sb2_kat.sb2, independently of the crate.
+
+The layout, AES-CTR counter and key blob follow the SB2.1 format written by elftosb and spsdk,
+using the cryptography package with fixed keys, nonce and timestamp:
+    python3 sb2_kat.py > sb2_kat.sb2
+"""
+
+import hashlib
+import hmac
+import struct
+import sys
+
+from cryptography.hazmat.primitives.ciphers import Cipher, algorithms, modes
+from cryptography.hazmat.primitives.keywrap import aes_key_wrap
+
+KEK = bytes(range(0x00, 0x20))
+DEK = bytes(range(0x20, 0x40))
+MAC_KEY = bytes(range(0x40, 0x60))
+# The low byte of the little endian counter wraps within the file.
+NONCE = bytes(range(0xA0, 0xAC)) + bytes([0xF8, 0xFF, 0x00, 0x00])
+TIMESTAMP = 0x0002_9A4E_5C3B_1000
+
+
+def crc32(data):
+    crc = 0xFFFFFFFF
+    for byte in data:
+        crc ^= byte << 24
+        for _ in range(8):
+            crc = ((crc << 1) ^ 0x04C11DB7 if crc & 0x80000000 else crc << 1) & 0xFFFFFFFF
+    return crc
+
+
+def command(tag, flags, address, count, data):
+    body = struct.pack("<BHIII", tag, flags, address, count, data)
+    return bytes([(0x5A + sum(body)) & 0xFF]) + body
+
+
+def encrypt(data, block):
+    counter = struct.unpack("<I", NONCE[12:])[0]
+    out = b""
+    for i in range(0, len(data), 16):
+        iv = NONCE[:12] + struct.pack("<I", (counter + block + i // 16) & 0xFFFFFFFF)
+        encryptor = Cipher(algorithms.AES(DEK), modes.CTR(iv)).encryptor()
+        out += encryptor.update(data[i : i + 16]) + encryptor.finalize()
+    return out
+
+
+load = bytes((i * 7) & 0xFF for i in range(40))
+load_padded = load + bytes(-len(load) % 16)
+commands = (
+    command(0x07, 0, 0, 0x1000, 0)
+    + command(0x02, 0, 0, len(load), crc32(load_padded))
+    + load_padded
+    + command(0x04, 0x2, 0x101, 0x2000_8000, 0x1234_5678)
+    + command(0x08, 0, 0, 0, 0)
+)
+
+header_blocks = 6
+key_blob_block = (0x60 + 16 + 32) // 16
+tag_block = key_blob_block + 5
+length = 2 + len(commands) // 16
+image_blocks = tag_block + 1 + length
+
+header = (
+    NONCE
+    + bytes(4)
+    + b"STMP"
+    + bytes([2, 1])
+    + struct.pack("<HIIII", 0, image_blocks, tag_block, 0, 0)
+    + struct.pack("<HHHH", header_blocks, key_blob_block, 5, 1)
+    + b"sgtl"
+    + struct.pack("<Q", TIMESTAMP)
+    + struct.pack("<HHHHHH", 1, 0, 2, 0, 3, 0)
+    + struct.pack("<HHHHHH", 4, 0, 5, 0, 6, 0)
+    + struct.pack("<H", 7)
+)
+header += bytes(0x60 - len(header))
+section_header = struct.pack("<IIII", 0, tag_block, length, 1)
+
+data = header + section_header
+data += hmac.new(MAC_KEY, data, hashlib.sha256).digest()
+data += aes_key_wrap(KEK, DEK + MAC_KEY) + bytes(8)
+data += encrypt(command(0x01, 0x1, 0, length, 1), tag_block)
+encrypted = encrypt(commands, tag_block + 3)
+data += hmac.new(MAC_KEY, encrypted, hashlib.sha256).digest() + encrypted
+
+assert len(data) == image_blocks * 16
+sys.stdout.buffer.write(data)
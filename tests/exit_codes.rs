@@ -0,0 +1,66 @@
+//! Exit codes of the command line tool, as documented in the README.
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Write an image of the given 32 bits words to a file of the temporary directory.
+fn image_file(name: &str, words: &[u32]) -> PathBuf {
+    let path =
+        std::env::temp_dir().join(format!("lpc_checksum-{}-{}.bin", std::process::id(), name));
+    let data = words
+        .iter()
+        .flat_map(|word| word.to_le_bytes())
+        .collect::<Vec<_>>();
+
+    fs::write(&path, data).unwrap();
+
+    path
+}
+
+/// Run the tool on the given file, returning its exit code.
+fn run(path: &PathBuf, arguments: &[&str]) -> i32 {
+    Command::new(env!("CARGO_BIN_EXE_lpc_checksum"))
+        .args(arguments)
+        .arg(path)
+        .output()
+        .unwrap()
+        .status
+        .code()
+        .unwrap()
+}
+
+#[test]
+fn insert_then_verify() {
+    let path = image_file(
+        "insert",
+        &[0x1000_2000, 0x101, 0x103, 0x105, 0x107, 0x109, 0x10B, 0],
+    );
+
+    assert_eq!(run(&path, &["-p", "LPC1700", "--verify"]), 1);
+    assert_eq!(run(&path, &["-p", "LPC1700"]), 0);
+    assert_eq!(run(&path, &["-p", "LPC1700", "--verify"]), 0);
+    assert_eq!(fs::read(&path).unwrap()[28..], 0xEFFF_D9DCu32.to_le_bytes());
+
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn image_too_short() {
+    let path = image_file("short", &[0x1000_2000, 0x101, 0x103, 0x105]);
+
+    assert_eq!(run(&path, &["-p", "LPC1700"]), 5);
+    assert_eq!(fs::read(&path).unwrap().len(), 16);
+
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn unknown_processor() {
+    let path = image_file("unknown", &[0; 8]);
+
+    assert_eq!(run(&path, &["-p", "ABC123"]), 7);
+    assert_eq!(fs::read(&path).unwrap(), [0; 32]);
+
+    fs::remove_file(path).unwrap();
+}